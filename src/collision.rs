@@ -1,36 +1,30 @@
 use std::ops::*;
+use crate::world_update::ObjId;
+use serde::{Serialize, Deserialize};
 
 pub type Coord = f32;
 pub type Dist = f32;
 pub type Amount = f32;
-#[derive(Copy, Clone, Debug)] // maybe we can get rid of that later and use reference for points
+#[derive(Copy, Clone, Debug, Serialize, Deserialize)] // maybe we can get rid of that later and use reference for points
 pub struct Point { pub x : Coord, pub y : Coord }
 pub type Coords = Point;
 pub type Size = Point;
 pub type Direction = Point;
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CircleBounds { pub coords : Coords, pub r : Dist }
 #[derive(Debug)]
 pub struct RectBounds { pub coords : Coords, pub size: Size }
 
-const WORLD_SIZE : (usize, usize) = (1024, 768);
-const GRID_CELL_SIZE : usize = 30;
-const GRID_SIZE : (usize, usize) = (WORLD_SIZE.0 / GRID_CELL_SIZE, WORLD_SIZE.1 / GRID_CELL_SIZE );
+pub const GRID_CELL_SIZE : Dist = 30.0;
 
-pub struct CollisionGrid { objects : [[Box<[i32]>; GRID_SIZE.1] ; GRID_SIZE.0] }
+// every object is registered in each cell its bounding rect touches
+#[derive(Debug)]
+pub struct CollisionGrid { objects : Vec<Vec<ObjId>>, cell_size : Dist, cells : (usize, usize) }
 
 pub trait Traverser<T> {
-	fn each(&self, closure : &Fn(&T)) {
-		self.reduce(Void{}, &|_ : Void, val| {
-			closure(val);
-			Void{}
-		});
-	}
 	fn reduce<U>(&self, default : U, closure : &Fn(U, &T) -> U) -> U;
 }
 
-struct Void {}
-
 impl<T> Traverser<T> for [T] {
 	fn reduce<U>(&self, default: U, closure: &Fn(U, &T) -> U) -> U {
 		let mut result = default;
@@ -59,19 +53,67 @@ impl Traverser<(usize, usize)> for GridRange {
 }
 
 impl CollisionGrid {
-//	fn addObj(&mut self, obj : CircleBounds) {
-//		obj.rectBounds.each { |(x,y)|
-//
-//		}
-//	}
-//
-//	fn rmObj() {
-//
-//	}
-//
-//	fn updObj() {
-//
-//	}
+	pub fn new(size : &Size, cell_size : Dist) -> CollisionGrid {
+		let cells = ((size.x / cell_size).ceil().max(1.0) as usize, (size.y / cell_size).ceil().max(1.0) as usize);
+		CollisionGrid { objects : vec![vec![]; cells.0 * cells.1], cell_size, cells }
+	}
+
+	pub fn cell_size(&self) -> Dist { self.cell_size }
+
+	fn cell(height : usize, (x, y) : &(usize, usize)) -> usize { x * height + y }
+
+	fn grid_coord(&self, c : Coord, cells : usize) -> usize {
+		((c / self.cell_size).max(0.0) as usize).min(cells - 1)
+	}
+
+	fn range(&self, area : &CircleBounds) -> GridRange {
+		GridRange {
+			xmin: self.grid_coord(area.coords.x - area.r, self.cells.0),
+			xmax: self.grid_coord(area.coords.x + area.r, self.cells.0),
+			ymin: self.grid_coord(area.coords.y - area.r, self.cells.1),
+			ymax: self.grid_coord(area.coords.y + area.r, self.cells.1),
+		}
+	}
+
+	pub fn add_obj(&mut self, id : ObjId, obj : &CircleBounds) {
+		let (range, height) = (self.range(obj), self.cells.1);
+		range.reduce(&mut self.objects, &|objects, xy| {
+			objects[CollisionGrid::cell(height, xy)].push(id);
+			objects
+		});
+	}
+
+	pub fn rm_obj(&mut self, id : ObjId, obj : &CircleBounds) {
+		let (range, height) = (self.range(obj), self.cells.1);
+		range.reduce(&mut self.objects, &|objects, xy| {
+			objects[CollisionGrid::cell(height, xy)].retain(|i| *i != id);
+			objects
+		});
+	}
+
+	pub fn upd_obj(&mut self, id : ObjId, old : &CircleBounds, new : &CircleBounds) {
+		self.rm_obj(id, old);
+		self.add_obj(id, new);
+	}
+
+	// like query, but without sorting, so an object spanning several cells shows up once per cell
+	pub fn candidates<'a>(&'a self, area : &CircleBounds) -> impl Iterator<Item=ObjId> + 'a {
+		let (range, height) = (self.range(area), self.cells.1);
+		let (ymin, ymax) = (range.ymin, range.ymax);
+		(range.xmin..=range.xmax).flat_map(move |x| (ymin..=ymax).
+			flat_map(move |y| self.objects[CollisionGrid::cell(height, &(x, y))].iter().cloned()))
+	}
+
+	// candidates whose cells overlap the area, sorted by id; exact checks are up to the caller
+	pub fn query(&self, area : &CircleBounds) -> Vec<ObjId> {
+		let mut result = self.range(area).reduce(vec![], &|mut result, xy| {
+			result.extend_from_slice(&self.objects[CollisionGrid::cell(self.cells.1, xy)]);
+			result
+		});
+		result.sort();
+		result.dedup();
+		result
+	}
 }
 
 impl RectBounds {
@@ -98,7 +140,6 @@ impl Point {
 
 impl CircleBounds {
 	pub fn on_layer(&self, layer : &RectBounds, dist_from_edge : Dist) -> bool {
-		let v = [1,2,3];
 		!(self.coords.x + self.r + dist_from_edge < layer.coords.x ||
 		self.coords.x - self.r - dist_from_edge > layer.coords.x + layer.size.x ||
 		self.coords.y + self.r + dist_from_edge < layer.coords.y ||
@@ -108,15 +149,6 @@ impl CircleBounds {
 	pub fn collides_with(&self, target : &CircleBounds) -> bool {
 		target.coords.dist(&self.coords) <= self.r + target.r
 	}
-
-//	pub fn rectBounds(&self : CircleBounds) -> GridRange {
-//		GridRange {
-//			xmin: (obj.coords.x - obj.r) as usize / GRID_CELL_SIZE,
-//			xmax: (obj.coords.x + obj.r) as usize / GRID_CELL_SIZE,
-//			ymin: (obj.coords.y - obj.r) as usize / GRID_CELL_SIZE,
-//			ymax: (obj.coords.y + obj.r) as usize / GRID_CELL_SIZE,
-//		}
-//	}
 }
 
 impl Sub for Point {
@@ -142,10 +174,104 @@ where T : Iterator<Item=&'a CircleBounds> {
 	(avoid.norm().ort() - avoid).multf(1.0/ count.max(1) as Dist)
 }
 
+// where a pursuer moving at `speed` meets a target keeping its velocity, or the target's next position if it can't be caught
+pub fn intercept(from : &Point, speed : Dist, target : &Point, velocity : &Direction) -> Point {
+	let offset = *target - *from;
+	let (a, b, c) = (velocity.mults(velocity) - speed.powi(2), 2.0 * offset.mults(velocity), offset.mults(&offset));
+	let time = if a.abs() < 0.0001 {
+		if b < 0.0 { -c / b } else { -1.0 }
+	} else {
+		let discriminant = b.powi(2) - 4.0 * a * c;
+		if discriminant < 0.0 { -1.0 } else {
+			let (t1, t2) = ((-b - discriminant.sqrt()) / (2.0 * a), (-b + discriminant.sqrt()) / (2.0 * a));
+			if t1 > 0.0 && (t1 < t2 || t2 <= 0.0) { t1 } else { t2 }
+		}
+	};
+	*target + velocity.multf(time.max(1.0))
+}
+
 pub fn move_to_target<'a, T>(bounds : &CircleBounds, target : &Point, obstacles : &mut T, speed : Dist) -> Point
 where T : Iterator<Item=&'a CircleBounds> {
 	let avoid_direction = avoid_collision(bounds, target, obstacles);
 	let direction = if avoid_direction.len() < 0.1 { (*target - bounds.coords).norm() } else { avoid_direction };
 
 	direction.multf(speed)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn circle(x : Coord, y : Coord, r : Dist) -> CircleBounds { CircleBounds { coords : Point::new(x, y), r } }
+
+	fn grid() -> CollisionGrid { CollisionGrid::new(&Point::new(100.0, 100.0), 10.0) }
+
+	#[test]
+	fn query_dedups_objects_spanning_several_cells() {
+		let mut grid = grid();
+		grid.add_obj(2, &circle(50.0, 50.0, 15.0));
+		grid.add_obj(1, &circle(55.0, 55.0, 1.0));
+		assert_eq!(grid.query(&circle(50.0, 50.0, 20.0)), vec![1, 2]);
+		assert!(grid.candidates(&circle(50.0, 50.0, 20.0)).filter(|id| *id == 2).count() > 1);
+	}
+
+	#[test]
+	fn objects_spanning_several_cells_are_found_from_each() {
+		let mut grid = grid();
+		grid.add_obj(1, &circle(50.0, 50.0, 15.0));
+		assert_eq!(grid.query(&circle(36.0, 36.0, 0.1)), vec![1]);
+		assert_eq!(grid.query(&circle(64.0, 64.0, 0.1)), vec![1]);
+		assert!(grid.query(&circle(80.0, 80.0, 0.1)).is_empty());
+	}
+
+	#[test]
+	fn rm_obj_forgets_every_cell() {
+		let mut grid = grid();
+		let bounds = circle(50.0, 50.0, 15.0);
+		grid.add_obj(1, &bounds);
+		grid.add_obj(2, &bounds);
+		grid.rm_obj(1, &bounds);
+		assert_eq!(grid.query(&circle(50.0, 50.0, 50.0)), vec![2]);
+	}
+
+	#[test]
+	fn upd_obj_moves_between_cells() {
+		let mut grid = grid();
+		let (old, new) = (circle(5.0, 5.0, 1.0), circle(95.0, 95.0, 1.0));
+		grid.add_obj(1, &old);
+		grid.upd_obj(1, &old, &new);
+		assert!(grid.query(&old).is_empty());
+		assert_eq!(grid.query(&new), vec![1]);
+	}
+
+	#[test]
+	fn out_of_map_coords_are_clamped_to_edge_cells() {
+		let mut grid = grid();
+		grid.add_obj(1, &circle(-20.0, 120.0, 1.0));
+		assert_eq!(grid.query(&circle(1.0, 99.0, 0.1)), vec![1]);
+		assert_eq!(grid.query(&circle(-500.0, 500.0, 0.1)), vec![1]);
+		grid.rm_obj(1, &circle(-20.0, 120.0, 1.0));
+		assert!(grid.query(&circle(50.0, 50.0, 100.0)).is_empty());
+	}
+
+	#[test]
+	fn intercept_leads_a_moving_target() {
+		let meet = intercept(&Point::new(0.0, 0.0), 2.0, &Point::new(10.0, 0.0), &Point::new(0.0, 1.0));
+		assert!(meet.y > 0.0);
+		// both get there at the same time
+		let time = (meet - Point::new(10.0, 0.0)).len();
+		assert!((meet.len() / 2.0 - time).abs() < 0.01);
+	}
+
+	#[test]
+	fn intercept_aims_ahead_of_a_target_it_cant_catch() {
+		let meet = intercept(&Point::new(0.0, 0.0), 1.0, &Point::new(10.0, 0.0), &Point::new(2.0, 0.0));
+		assert!(meet.dist(&Point::new(12.0, 0.0)) < 0.01);
+	}
+
+	#[test]
+	fn intercept_of_a_standing_target_is_its_position() {
+		let meet = intercept(&Point::new(0.0, 0.0), 1.0, &Point::new(10.0, 5.0), &Point::init());
+		assert!(meet.dist(&Point::new(10.0, 5.0)) < 0.01);
+	}
+}
@@ -1,9 +1,10 @@
 use ggez::graphics::Color;
+use serde::Deserialize;
 
 // colors from https://www.rapidtables.com/web/color/RGB_Color.html
 // they might sue me though
 #[allow(dead_code)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
 pub enum ColorTone {
 	Maroon,
 	DarkRed,
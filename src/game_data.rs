@@ -0,0 +1,155 @@
+use crate::world_update::{GameObjBlueprint, BlueprintId, EffectType};
+use crate::world_gen::Tile;
+use crate::collision::{CircleBounds, Point, Size};
+use crate::colors::ColorTone;
+use crate::techniques::Technique;
+use serde::Deserialize;
+use std::fs::File;
+use std::ops::Deref;
+use std::sync::OnceLock;
+
+pub const DATA_PATH : &str = "data/blueprints.json"; // relative to the working directory, --data overrides it
+
+pub type ClanId = usize;
+
+// typos in the data file are errors rather than silently ignored fields
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GameData {
+	pub blueprints : Vec<GameObjBlueprint>,
+	#[serde(default)]
+	pub clans : Vec<Clan>,
+	pub spawn : Vec<Spawn>,
+	#[serde(default)]
+	pub techniques : Vec<Technique>,
+	#[serde(skip)]
+	hostility : Vec<Vec<bool>>,
+}
+
+// hostility is mutual, it's enough for one side to list the other as an enemy
+// home coords are shares of the map size, so clans keep their places on any map
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Clan { pub name : String, pub color : ColorTone, home : CircleBounds, #[serde(default)] pub enemies : Vec<String> }
+
+// spawned in order, no count means fill the map, clan members appear around their clan's home
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Spawn {
+	pub blueprint : String,
+	pub tile : Tile,
+	pub count : Option<i32>,
+	pub clan : Option<String>,
+	#[serde(skip)]
+	pub blueprint_id : BlueprintId, // resolved by GameData::validate
+	#[serde(skip)]
+	pub clan_id : Option<ClanId>,
+}
+
+impl Clan {
+	pub fn home(&self, map_size : &Size) -> CircleBounds {
+		CircleBounds { coords : Point::new(self.home.coords.x * map_size.x, self.home.coords.y * map_size.y), r : self.home.r }
+	}
+}
+
+impl GameData {
+	fn with_hostility(mut self) -> GameData {
+		self.hostility = self.clans.iter().map(|a| self.clans.iter().map(|b| {
+			a.enemies.contains(&b.name) || b.enemies.contains(&a.name)
+		}).collect()).collect();
+		self
+	}
+
+	// names are resolved to ids here, so nothing past loading has to deal with unknown ones
+	fn validate(mut self) -> Result<GameData, String> {
+		let unknown_technique = self.blueprints.iter().flat_map(|blueprint| blueprint.techniques.iter()).
+			find(|name| !self.techniques.iter().any(|technique| technique.name == **name)).
+			map(|name| format!("unknown technique: {}", name));
+		let unknown_enemy = self.clans.iter().flat_map(|clan| clan.enemies.iter()).
+			find(|name| self.clan_by_name(name).is_none()).
+			map(|name| format!("unknown clan in enemies: {}", name));
+		let no_period = self.techniques.iter().find(|technique| technique.on_hit.iter().any(|on_hit| match on_hit.effect {
+			EffectType::PeriodicEffect { period, .. } => period == 0,
+			_ => false
+		})).map(|technique| format!("periodic effect without a period in {}", technique.name));
+		if let Some(e) = unknown_technique.or(unknown_enemy).or(no_period) { return Err(e) }
+		for i in 0..self.blueprints.len() {
+			let remains = self.blueprints[i].remains.iter().map(|name| self.blueprint_id(name, "remains")).collect::<Result<_, _>>()?;
+			self.blueprints[i].remains_ids = remains;
+		}
+		for i in 0..self.spawn.len() {
+			let blueprint = self.blueprint_id(&self.spawn[i].blueprint, "spawn list")?;
+			let clan = self.spawn[i].clan.as_ref().map(|name| self.clan_by_name(name).ok_or_else(|| format!("unknown clan in spawn list: {}", name))).transpose()?;
+			self.spawn[i].blueprint_id = blueprint;
+			self.spawn[i].clan_id = clan;
+		}
+		Ok(self)
+	}
+
+	fn blueprint_id(&self, name : &str, used_in : &str) -> Result<BlueprintId, String> {
+		self.blueprints.iter().position(|blueprint| blueprint.name == name).ok_or_else(|| format!("unknown blueprint in {}: {}", used_in, name))
+	}
+
+	pub fn clan_by_name(&self, name : &str) -> Option<ClanId> {
+		self.clans.iter().position(|clan| clan.name == name)
+	}
+
+	pub fn hostile(&self, a : ClanId, b : ClanId) -> bool { self.hostility[a][b] }
+}
+
+fn read(path : &str) -> Result<GameData, String> {
+	File::open(path).map_err(|e| e.to_string()).
+		and_then(|file| serde_json::from_reader(file).map_err(|e| e.to_string())).
+		and_then(GameData::validate).
+		map(GameData::with_hostility)
+}
+
+static LOADED : OnceLock<GameData> = OnceLock::new();
+
+// everything reads the data through GAME_DATA once main has loaded it
+pub struct LoadedGameData;
+
+pub static GAME_DATA : LoadedGameData = LoadedGameData;
+
+impl Deref for LoadedGameData {
+	type Target = GameData;
+	fn deref(&self) -> &GameData { LOADED.get().expect("game data is used before it's loaded") }
+}
+
+pub fn load(path : &str) -> Result<(), String> {
+	let data = read(path)?;
+	LOADED.set(data).map_err(|_| "game data is already loaded".to_string())
+}
+
+// tests share the default data file, whichever of them comes first loads it
+#[cfg(test)]
+pub fn load_for_tests() {
+	LOADED.get_or_init(|| read(DATA_PATH).unwrap());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn hostility_is_mutual_and_clans_are_at_peace_with_themselves() {
+		load_for_tests();
+		let clan = |name| GAME_DATA.clan_by_name(name).unwrap();
+		let (wudang, emei, beggars) = (clan("Wudang"), clan("Emei"), clan("Beggar Sect"));
+		assert!(GAME_DATA.hostile(wudang, emei) && GAME_DATA.hostile(emei, wudang));
+		assert!(GAME_DATA.hostile(wudang, beggars) && GAME_DATA.hostile(beggars, wudang));
+		assert!(!GAME_DATA.hostile(wudang, wudang));
+	}
+
+	#[test]
+	fn unknown_names_are_rejected() {
+		let data = |spawn : &str| serde_json::from_str::<GameData>(&format!(r#"{{
+			"blueprints" : [{{ "name" : "Rock", "genus" : {{ "Plant" : "Big" }}, "min_dist" : 1.0, "radius" : {{ "start" : 1.0, "end" : 1.0 }},
+				"color" : "Black", "durability" : 1.0, "speed" : 0.0, "perception" : 0.0, "nutrition" : 0.0, "tasks" : [] }}],
+			"spawn" : [{}]
+		}}"#, spawn)).unwrap().validate().map(|_| ());
+		assert_eq!(data(r#"{ "blueprint" : "Rock", "tile" : "Floor" }"#), Ok(()));
+		assert_eq!(data(r#"{ "blueprint" : "Stone", "tile" : "Floor" }"#), Err("unknown blueprint in spawn list: Stone".to_string()));
+		assert_eq!(data(r#"{ "blueprint" : "Rock", "tile" : "Floor", "clan" : "Shaolin" }"#), Err("unknown clan in spawn list: Shaolin".to_string()));
+	}
+}
@@ -4,13 +4,71 @@ mod colors;
 mod collision;
 mod std_extended;
 mod world_render;
+mod world_save;
+mod game_data;
+mod techniques;
+mod soil;
 use self::world_gen::*;
 use self::collision::*;
 use self::world_render::*;
+use self::world_update::format_time;
+use self::game_data::DATA_PATH;
 use rand::{thread_rng, Rng};
+use std::str::FromStr;
 extern crate fps_counter;
 
+const USAGE : &str = "usage: wuxia_clan_war [--data <file>] [--seed <seed> [--width <w>] [--height <h>] [--cell-size <size>] | --load <file>] [--headless <ticks> [--save <file>]]";
+const FLAGS : &[&str] = &["--data", "--seed", "--width", "--height", "--cell-size", "--load", "--headless", "--save"]; // all take a value
+
 fn main() {
-	let world = generate_world(Point::new(1024.0, 768.0), 400);
-	ggez_loop(world);
+	run().unwrap_or_else(|e| fail(&format!("{}\n{}", e, USAGE)))
+}
+
+// flags are all parsed before anything starts, so a typo doesn't cost a generated world
+fn run() -> Result<(), String> {
+	let args : Vec<String> = std::env::args().collect();
+	if let Some(unknown) = args.iter().skip(1).step_by(2).find(|flag| !FLAGS.contains(&flag.as_str())) {
+		return Err(format!("unknown flag: {}", unknown))
+	}
+	let (load, seed, headless, save) = (arg::<String>("--load")?, arg("--seed")?, arg("--headless")?, arg("--save")?);
+	let data = arg("--data")?.unwrap_or_else(|| DATA_PATH.to_string());
+	let size = Point::new(arg("--width")?.unwrap_or(1024.0), arg("--height")?.unwrap_or(768.0));
+	let cell_size = arg("--cell-size")?.unwrap_or(GRID_CELL_SIZE);
+	if size.x <= 0.0 || size.y <= 0.0 || cell_size <= 0.0 { return Err("sizes must be positive".to_string()) }
+	game_data::load(&data).unwrap_or_else(|e| fail(&format!("failed to load {}: {}", data, e)));
+	let world = match load {
+		Some(path) => World::load(&path).unwrap_or_else(|e| fail(&format!("failed to load {}: {}", path, e))),
+		None => {
+			let seed = seed.unwrap_or_else(|| thread_rng().gen());
+			println!("seed: {}", seed);
+			generate_world(size, cell_size, seed)
+		}
+	};
+	match headless {
+		Some(ticks) => run_headless(world, ticks, save),
+		None => ggez_loop(world),
+	}
+	Ok(())
+}
+
+// None if the flag isn't given, an error if its value is missing or malformed
+fn arg<T : FromStr>(name : &str) -> Result<Option<T>, String> {
+	let args : Vec<String> = std::env::args().collect();
+	match args.iter().position(|a| a == name) {
+		Some(i) => args.get(i + 1).and_then(|v| v.parse().ok()).map(Some).
+			ok_or_else(|| format!("bad or missing value for {}", name)),
+		None => Ok(None)
+	}
+}
+
+fn run_headless(mut world : World, ticks : u64, save : Option<String>) {
+	for _ in 0..ticks { world.upd(); }
+	println!("time: {}", format_time(world.time));
+	world.census().iter().for_each(|(name, count)| println!("{}: {}", name, count));
+	save.iter().for_each(|path| world.save(path).unwrap_or_else(|e| fail(&format!("failed to save {}: {}", path, e))));
+}
+
+fn fail(message : &str) -> ! {
+	eprintln!("{}", message);
+	std::process::exit(1)
 }
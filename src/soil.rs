@@ -0,0 +1,34 @@
+use crate::collision::{Point, Size, Dist, Amount};
+use serde::{Serialize, Deserialize};
+
+pub const SOIL_CELL_SIZE : Dist = 16.0;
+pub const MAX_FERTILITY : Amount = 1.0;
+
+// fertility per cell, plants need it to spread
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Soil { fertility : Vec<Amount>, cell_size : Dist, cells : (usize, usize) }
+
+impl Soil {
+	pub fn new(size : &Size, cell_size : Dist) -> Soil {
+		let cells = ((size.x / cell_size).ceil().max(1.0) as usize, (size.y / cell_size).ceil().max(1.0) as usize);
+		Soil { fertility : vec![MAX_FERTILITY; cells.0 * cells.1], cell_size, cells }
+	}
+
+	fn cell(&self, p : &Point) -> usize {
+		let x = ((p.x / self.cell_size).max(0.0) as usize).min(self.cells.0 - 1);
+		let y = ((p.y / self.cell_size).max(0.0) as usize).min(self.cells.1 - 1);
+		x * self.cells.1 + y
+	}
+
+	pub fn fertility(&self, p : &Point) -> Amount { self.fertility[self.cell(p)] }
+
+	// positive to enrich, negative to exhaust
+	pub fn change(&mut self, p : &Point, amount : Amount) {
+		let cell = self.cell(p);
+		self.fertility[cell] = (self.fertility[cell] + amount).max(0.0).min(MAX_FERTILITY);
+	}
+
+	pub fn regrow(&mut self, amount : Amount) {
+		self.fertility.iter_mut().for_each(|f| *f = (*f + amount).min(MAX_FERTILITY));
+	}
+}
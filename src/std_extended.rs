@@ -1,10 +1,10 @@
-use super::{thread_rng, Rng};
+use super::Rng;
 use super::collision::Dist;
 use std::ops::Range;
 use num_iter::range;
 
-pub fn rng_range(range : &Range<Dist>) -> Dist {
-	if range.start >= range.end { range.start } else { thread_rng().gen_range(range.start, range.end) }
+pub fn rng_range<R : Rng>(rng : &mut R, range : &Range<Dist>) -> Dist {
+	if range.start >= range.end { range.start } else { rng.gen_range(range.start, range.end) }
 }
 
 pub fn index_iter<T>(v : &Vec<T>) -> impl Iterator<Item=usize> {
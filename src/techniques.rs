@@ -0,0 +1,82 @@
+use crate::collision::{CircleBounds, Point, Direction, Dist, Amount};
+use crate::world_update::{EffectType, FrameCount, moments};
+use crate::game_data::GAME_DATA;
+use serde::Deserialize;
+
+pub type TechniqueId = usize; // index in GameData::techniques
+
+// circles are centered on the user, cones open along the aim, angle is their full width in degrees
+#[derive(Debug, Clone, Deserialize)]
+pub enum Shape { Circle { r : Dist }, Cone { r : Dist, angle : f32 } }
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OnHit { #[serde(deserialize_with = "moments")] pub duration : FrameCount, pub effect : EffectType }
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Technique {
+	pub name : String,
+	pub qi_cost : Amount,
+	#[serde(deserialize_with = "moments")]
+	pub cooldown : FrameCount,
+	pub shape : Shape,
+	pub damage : Amount, // multiplied by the user's realm factor
+	#[serde(default)]
+	pub on_hit : Vec<OnHit>,
+}
+
+impl Technique {
+	pub fn by_name(name : &str) -> Option<TechniqueId> {
+		GAME_DATA.techniques.iter().position(|technique| technique.name == name)
+	}
+}
+
+impl Shape {
+	pub fn reach(&self) -> Dist {
+		match self { Shape::Circle { r } | Shape::Cone { r, .. } => *r }
+	}
+
+	pub fn covers(&self, origin : &Point, aim : &Direction, target : &CircleBounds) -> bool {
+		let offset = target.coords - *origin;
+		if offset.len() > self.reach() + target.r { return false }
+		match self {
+			Shape::Circle { .. } => true,
+			Shape::Cone { angle, .. } => offset.len() <= target.r || {
+				let half_width = angle.to_radians() / 2.0 + (target.r / offset.len()).asin();
+				aim.norm().mults(&offset.norm()) >= half_width.min(std::f32::consts::PI).cos()
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn circle(x : f32, y : f32, r : Dist) -> CircleBounds { CircleBounds { coords : Point::new(x, y), r } }
+
+	#[test]
+	fn circle_covers_targets_touching_its_reach() {
+		let (shape, origin, aim) = (Shape::Circle { r : 10.0 }, Point::init(), Point::new(1.0, 0.0));
+		assert!(shape.covers(&origin, &aim, &circle(-12.0, 0.0, 3.0)));
+		assert!(!shape.covers(&origin, &aim, &circle(0.0, 14.0, 3.0)));
+	}
+
+	#[test]
+	fn cone_covers_targets_along_the_aim_only() {
+		let (shape, origin, aim) = (Shape::Cone { r : 10.0, angle : 40.0 }, Point::init(), Point::new(1.0, 0.0));
+		assert!(shape.covers(&origin, &aim, &circle(8.0, 2.0, 0.5)));
+		assert!(!shape.covers(&origin, &aim, &circle(-8.0, 0.0, 0.5)));
+		assert!(!shape.covers(&origin, &aim, &circle(5.0, 5.0, 0.5)));
+		assert!(!shape.covers(&origin, &aim, &circle(12.0, 0.0, 1.0)));
+	}
+
+	#[test]
+	fn cone_covers_big_targets_overlapping_its_edge() {
+		let (shape, origin, aim) = (Shape::Cone { r : 10.0, angle : 20.0 }, Point::init(), Point::new(1.0, 0.0));
+		assert!(!shape.covers(&origin, &aim, &circle(5.0, 5.0, 1.0)));
+		assert!(shape.covers(&origin, &aim, &circle(5.0, 5.0, 4.5)));
+		assert!(shape.covers(&origin, &aim, &circle(1.0, 1.0, 2.0)));
+	}
+}
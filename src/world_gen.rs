@@ -3,7 +3,16 @@ use super::std_extended::*;
 use crate::world_update::GameObj;
 use crate::world_update::GameObjBlueprint;
 use ordered_float::OrderedFloat;
-use crate::world_update::FrameCount;
+use crate::world_update::ObjId;
+use crate::game_data::GAME_DATA;
+use crate::game_data::ClanId;
+use crate::soil::{Soil, SOIL_CELL_SIZE};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::ops::Range;
+use rand_pcg::Pcg32; // unlike StdRng, a named algorithm yields the same numbers across rand versions
+use rand::SeedableRng;
 use ggez::graphics::Drawable;
 
 pub enum Bounds<'a> {
@@ -12,62 +21,190 @@ pub enum Bounds<'a> {
 }
 
 // entities on different layers do not collide
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
 pub enum Tile { Floor = 0, Middle = 1, Sky = 2 }
 
 #[derive(Debug)]
 pub struct RectTile { pub tile : Tile, pub bounds : RectBounds }
 
 #[derive(Debug)]
-pub struct World { pub size : Size, pub objects : Vec<GameObj>, pub time : u64 }
-
-pub fn generate_world(size : Size, wanderers : i32) -> World {
-	let mut world = World { size, objects: vec![], time : 0 };
-	add_objects(&mut world, Tile::Middle, &GameObjBlueprint::TREE, None);
-	add_objects(&mut world, Tile::Floor, &GameObjBlueprint::HARE, Some(100));
-	add_objects(&mut world, Tile::Middle, &GameObjBlueprint::WOLF, Some(10));
-	add_objects(&mut world, Tile::Floor, &GameObjBlueprint::GRASS, Some(1000));
-	add_objects(&mut world, Tile::Middle, &GameObjBlueprint::WANDERER, Some(wanderers));
-	add_objects(&mut world, Tile::Middle, &GameObjBlueprint::PLAYER, Some(1));
+pub struct World {
+	pub size : Size,
+	pub objects : BTreeMap<ObjId, GameObj>,
+	pub time : u64,
+	pub soil : Soil,
+	next_id : ObjId,
+	grid : CollisionGrid, // kept in sync by insert/remove/move_obj
+	rng : RefCell<Pcg32>, // the only source of randomness, so a seed replays the whole simulation
+}
+
+impl World {
+	pub fn new(size : Size, cell_size : Dist, seed : u64) -> World {
+		let (grid, soil) = (CollisionGrid::new(&size, cell_size), Soil::new(&size, SOIL_CELL_SIZE));
+		World { size, objects: BTreeMap::new(), time : 0, soil, next_id : 0, grid, rng : RefCell::new(Pcg32::seed_from_u64(seed)) }
+	}
+
+	// rebuilds the grid, ids are kept as they are
+	pub fn restore(size : Size, cell_size : Dist, time : u64, next_id : ObjId, rng : Pcg32, soil : Soil, objects : Vec<GameObj>) -> World {
+		let mut world = World::new(size, cell_size, 0);
+		world.rng = RefCell::new(rng);
+		world.time = time;
+		world.soil = soil;
+		world.next_id = next_id;
+		objects.into_iter().for_each(|obj| {
+			world.grid.add_obj(obj.id, &obj.bounds);
+			world.objects.insert(obj.id, obj);
+		});
+		world
+	}
+
+	pub fn next_id(&self) -> ObjId { self.next_id }
+
+	pub fn cell_size(&self) -> Dist { self.grid.cell_size() }
+
+	// snapshots keep the rng as it is, so a loaded world goes on exactly like the saved one
+	pub fn rng_state(&self) -> Pcg32 { self.rng.borrow().clone() }
+
+	pub fn rng_range(&self, range : &Range<Dist>) -> Dist {
+		rng_range(&mut *self.rng.borrow_mut(), range)
+	}
+
+	pub fn insert(&mut self, mut obj : GameObj) -> ObjId {
+		let id = self.next_id;
+		self.next_id += 1;
+		obj.id = id;
+		self.grid.add_obj(id, &obj.bounds);
+		self.objects.insert(id, obj);
+		id
+	}
+
+	pub fn remove(&mut self, id : ObjId) -> Option<GameObj> {
+		let obj = self.objects.remove(&id);
+		obj.iter().for_each(|obj| self.grid.rm_obj(id, &obj.bounds));
+		obj
+	}
+
+	pub fn move_obj(&mut self, id : ObjId, coords : Point) {
+		let obj = self.objects.get_mut(&id).unwrap();
+		let old = obj.bounds.clone();
+		obj.velocity = coords - old.coords;
+		obj.bounds.coords = coords;
+		self.grid.upd_obj(id, &old, &obj.bounds);
+	}
+
+	pub fn clamp(&self, p : &Point) -> Point {
+		Point::new(p.x.max(0.0).min(self.size.x), p.y.max(0.0).min(self.size.y))
+	}
+
+	pub fn contains(&self, p : &Point) -> bool {
+		p.x >= 0.0 && p.x <= self.size.x && p.y >= 0.0 && p.y <= self.size.y
+	}
+
+	pub fn resize(&mut self, id : ObjId, r : Dist) {
+		let obj = self.objects.get_mut(&id).unwrap();
+		let old = obj.bounds.clone();
+		obj.bounds.r = r;
+		self.grid.upd_obj(id, &old, &obj.bounds);
+	}
+
+	// bounds must not be changed through this, use move_obj instead
+	pub fn obj_mut(&mut self, id : ObjId) -> &mut GameObj {
+		self.objects.get_mut(&id).unwrap()
+	}
+
+	// population per blueprint name
+	pub fn census(&self) -> BTreeMap<&'static str, usize> {
+		self.objects.values().fold(BTreeMap::new(), |mut result, obj| {
+			*result.entry(obj.blueprint.name.as_str()).or_insert(0) += 1;
+			result
+		})
+	}
+
+	// objects that might intersect the area
+	pub fn near<'a>(&'a self, area : &CircleBounds) -> impl Iterator<Item=&'a GameObj> {
+		self.grid.query(area).into_iter().map(move |id| &self.objects[&id])
+	}
+
+	// widens the search area until something is found or the range is covered
+	pub fn nearest<F>(&self, coords : &Point, range : Dist, pred : F) -> Option<&GameObj> where F : Fn(&GameObj) -> bool {
+		let mut r = self.grid.cell_size().min(range);
+		loop {
+			let found = self.grid.candidates(&CircleBounds { coords : *coords, r }).map(|id| &self.objects[&id]).
+				filter(|obj| obj.bounds.coords.dist(coords) <= r && pred(obj)).
+				min_by_key(|obj| (OrderedFloat(obj.bounds.coords.dist(coords)), obj.id));
+			if found.is_some() || r >= range { return found }
+			r = (r * 2.0).min(range);
+		}
+	}
+}
+
+pub fn generate_world(size : Size, cell_size : Dist, seed : u64) -> World {
+	let mut world = World::new(size, cell_size, seed);
+	GAME_DATA.spawn.iter().for_each(|spawn| {
+		add_objects(&mut world, spawn.tile.clone(), &GAME_DATA.blueprints[spawn.blueprint_id], spawn.count, spawn.clan_id);
+	});
 	world
 }
 
-fn add_object(size : &Size, objects : &mut Vec<GameObj>, blueprint : &'static GameObjBlueprint, time : FrameCount) -> bool {
-	let bounds = gen_circle_bounds(size, None, objects, blueprint);
-	if bounds.is_some() { objects.push(GameObj::from(&blueprint, bounds.unwrap(), time)); true } else { false }
+fn add_object(w : &mut World, blueprint : &'static GameObjBlueprint, clan : Option<ClanId>) -> bool {
+	let home = clan.map(|clan| GAME_DATA.clans[clan].home(&w.size));
+	let bounds = gen_circle_bounds(w, home.as_ref(), blueprint);
+	if bounds.is_some() {
+		let mut obj = GameObj::with_random_age(&blueprint, bounds.unwrap(), w);
+		obj.clan = clan;
+		w.insert(obj);
+		true
+	} else { false }
 }
 
-pub fn add_objects(w : &mut World, tile : Tile, blueprint : &'static GameObjBlueprint, count : Option<i32>) {
-	let objects = &mut w.objects;
-	let time = w.time;
+pub fn add_objects(w : &mut World, tile : Tile, blueprint : &'static GameObjBlueprint, count : Option<i32>, clan : Option<ClanId>) {
 	if count.is_none() {
-		loop { if !add_object(&w.size, objects, blueprint, time) { break } }
+		loop { if !add_object(w, blueprint, clan) { break } }
 	} else {
-		for _ in 0..count.unwrap() { add_object(&w.size, objects, blueprint, time); }
+		for _ in 0..count.unwrap() { add_object(w, blueprint, clan); }
 	}
 }
 
 //maybe support circular bounds too
 pub fn gen_circle_bounds(
-	size : &Size,
+	w : &World,
 	center_bounds : Option<&CircleBounds>,
-	objects : &Vec<GameObj>,
 	blueprint : &'static GameObjBlueprint
 ) -> Option<CircleBounds> {
+	let size = &w.size;
 	for _ in 0..100 {
-		let r = rng_range(&blueprint.radius);
+		let r = blueprint.radius.end; // room for it to grow up, the actual radius depends on age
 		let coords = match center_bounds {
 			None => Point::new(
-				rng_range(&(r + blueprint.min_dist..size.x - r - blueprint.min_dist)),
-				rng_range(&(r + blueprint.min_dist..size.y - r - blueprint.min_dist))
+				w.rng_range(&(r + blueprint.min_dist..size.x - r - blueprint.min_dist)),
+				w.rng_range(&(r + blueprint.min_dist..size.y - r - blueprint.min_dist))
 			),
 			Some(v) => {
-				let (angle, dist) = (rng_range(&(0.0..2.0*std::f32::consts::PI)), rng_range(&(0.0..v.r)));
+				let (angle, dist) = (w.rng_range(&(0.0..2.0*std::f32::consts::PI)), w.rng_range(&(0.0..v.r)));
 				Point::new(v.coords.x + angle.sin() * dist, v.coords.y + angle.cos() * dist)
 			}
 		};
 		let bounds = CircleBounds { coords, r };
-		if !(objects.iter().any(|obs| { obs.bounds.coords.dist(&bounds.coords) < obs.bounds.r + bounds.r + blueprint.min_dist})) { return Some(bounds) }
+		if !(w.near(&CircleBounds { r : r + blueprint.min_dist, ..bounds }).any(|obs| { obs.bounds.coords.dist(&bounds.coords) < obs.bounds.r + bounds.r + blueprint.min_dist})) { return Some(bounds) }
 	}
 	return None
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::game_data::load_for_tests;
+
+	fn run(seed : u64) -> String {
+		let mut world = generate_world(Point::new(300.0, 300.0), GRID_CELL_SIZE, seed);
+		for _ in 0..100 { world.upd(); }
+		format!("{:?}", world.objects)
+	}
+
+	#[test]
+	fn a_seed_replays_the_same_simulation() {
+		load_for_tests();
+		assert_eq!(run(3), run(3));
+		assert_ne!(run(3), run(4));
+	}
+}
@@ -1,6 +1,12 @@
 use super::world_gen::*;
 use super::colors::*;
 use crate::collision::*;
+use crate::world_update::ObjId;
+use crate::world_update::REALMS;
+use crate::world_update::MEDITATION;
+use crate::world_update::Task;
+use crate::world_update::daylight;
+use crate::game_data::GAME_DATA;
 use ggez::graphics::MeshBuilder;
 use ggez::graphics::DrawMode;
 use ggez::graphics::Rect;
@@ -18,24 +24,31 @@ use itertools::Itertools;
 use ggez::input::keyboard::KeyMods;
 use ggez::event::EventsLoop;
 use ggez::graphics::{Text, TextFragment};
+use ggez::conf::WindowMode;
+
+const SNAPSHOT_PATH : &str = "world.json";
+const NIGHT_TINT : f32 = 0.6; // opacity of the dark overlay at midnight
 
 pub struct RenderedShape<'a> { color : Color, bounds : Bounds<'a> }
 
 impl World {
-	pub fn to_scene(&mut self) -> Vec<RenderedShape> {
-		self.objects.sort_by_key(|obj| OrderedFloat(obj.blueprint.speed));
+	pub fn to_scene(&self) -> Vec<RenderedShape> {
 		Some(RenderedShape {
 			bounds : Bounds::Rect { size : &self.size },
 			color: solid_color(&ColorTone::LimeGreen)
 		}).into_iter().
-			chain(self.objects.iter().map(|obj| RenderedShape {
+			chain(self.objects.values().sorted_by_key(|obj| OrderedFloat(obj.blueprint.speed)).map(|obj| RenderedShape {
 				bounds: Bounds::Circle { v: &obj.bounds },
-				color: solid_color(&obj.blueprint.color)
+				color: solid_color(obj.clan.map(|clan| &GAME_DATA.clans[clan].color).unwrap_or(&obj.blueprint.color))
+			})).chain(Some(RenderedShape {
+				bounds : Bounds::Rect { size : &self.size },
+				color : Color::new(0.0, 0.0, 0.1, (1.0 - daylight(self.time)) * NIGHT_TINT)
 			})).collect()
 	}
 }
 
-struct WorldWithDebugInfo { world : World, fps : FPSCounter, controls : ControlsState }
+// there's no player to steer once it's dead, or when the map has no room left for one
+struct WorldWithDebugInfo { world : World, fps : FPSCounter, controls : ControlsState, player : Option<ObjId>, selected : Option<ObjId> }
 
 struct ControlsState { up : bool, down : bool, left : bool, right : bool, superhot : bool, mouse : Coords }
 
@@ -63,40 +76,60 @@ impl ControlsState {
 }
 
 
+impl WorldWithDebugInfo {
+	fn player_key(&mut self, player : ObjId, keycode : KeyCode) {
+		if keycode == KeyCode::M {
+			let until = self.world.time + MEDITATION;
+			self.world.obj_mut(player).tasks.push(Task::Meditate { until });
+		}
+		// P picks up whatever is under the mouse, D drops, E eats and G gives the last picked item to it
+		let last = self.world.objects[&player].inventory.len().saturating_sub(1);
+		match (keycode, self.selected) {
+			(KeyCode::P, Some(item)) => self.world.pick_up(player, item),
+			(KeyCode::D, _) => self.world.drop_item(player, last),
+			(KeyCode::E, _) => self.world.eat_item(player, last),
+			(KeyCode::G, Some(to)) => self.world.give_item(player, last, to),
+			_ => {}
+		}
+		// technique slots of the player's blueprint, aimed at the mouse
+		let slot = match keycode { KeyCode::Key1 => Some(0), KeyCode::Key2 => Some(1), KeyCode::Key3 => Some(2), KeyCode::Key4 => Some(3), _ => None };
+		if let Some(technique) = slot.and_then(|slot| self.world.objects[&player].technique(slot)) {
+			let aim = self.controls.mouse - self.world.objects[&player].bounds.coords;
+			self.world.strike(player, technique, &aim);
+		}
+	}
+}
+
 impl EventHandler for WorldWithDebugInfo {
 	fn update(&mut self, ctx: &mut Context) -> GameResult {
 //    update player
-		let i = self.world.objects.iter().find_position( |item| item.blueprint.name == "Player").unwrap().0;
-		let mut player = &self.world.objects[i];
-		let speed = player.blueprint.speed;
 		let direction = self.controls.direction();
-		let upd_coords = player.move_to(&self.world, &(player.bounds.coords + direction.multf(speed)));
-		let mut player_mut = &mut self.world.objects[i];
-		player_mut.bounds.coords = upd_coords;
+		if let Some(player) = self.player.and_then(|id| self.world.objects.get(&id)) {
+			let speed = player.speed(self.world.time);
+			let upd_coords = player.move_to(&self.world, &(player.bounds.coords + direction.multf(speed)));
+			self.world.move_obj(player.id, upd_coords);
+		}
 //    update world
 		if (direction.len() > 0.001 || !self.controls.superhot) { /*println!("{}", self.fps.tick());*/ self.world.update(ctx) } else { Ok(()) }
 	}
 
 	fn draw(&mut self, ctx: &mut Context) -> GameResult {
-		let result = self.world.draw(ctx);
-		let selected = self.world.objects.iter().for_each( |obj|
-			if obj.bounds.collides_with(&CircleBounds { coords : self.controls.mouse, r : 0.1 }) {
-				let text = Text::new(format!("durability: {}", &obj.durability));
-				let coverRect = match text.dimensions(ctx) { (x, y) => {
-//					println!("{} {}", x, y);
-					let mb = MeshBuilder::new().rectangle(
-						DrawMode::Fill,
-						Rect::new(10.0, 10.0, x as f32, y as f32),
-						solid_color(&ColorTone::Black)
-					).build(ctx).unwrap();
-					draw(ctx, &mb, (point2(&self.controls.mouse),));
-					draw(ctx, &text, (point2(&(self.controls.mouse + Point::new(10.0, 10.0))),));
-
-				}};
-			}
-		);
-		let result = present(ctx);
-		result
+		self.world.draw(ctx)?;
+		if let Some(obj) = self.selected.and_then(|id| self.world.objects.get(&id)) {
+			let text = Text::new(format!(
+				"durability: {}\nsatiety: {}\nqi: {}\nrealm: {}\nitems: {}", &obj.durability, &obj.satiety, &obj.qi, REALMS[obj.realm],
+				obj.inventory.iter().map(|item| &item.blueprint.name).join(", ")
+			));
+			let (x, y) = text.dimensions(ctx);
+			let mb = MeshBuilder::new().rectangle(
+				DrawMode::Fill,
+				Rect::new(10.0, 10.0, x as f32, y as f32),
+				solid_color(&ColorTone::Black)
+			).build(ctx)?;
+			draw(ctx, &mb, (point2(&self.controls.mouse),))?;
+			draw(ctx, &text, (point2(&(self.controls.mouse + Point::new(10.0, 10.0))),))?;
+		}
+		present(ctx)
 	}
 
 	fn key_down_event(
@@ -106,6 +139,15 @@ impl EventHandler for WorldWithDebugInfo {
 		_keymods: KeyMods,
 		_repeat: bool,
 	) {
+		if keycode == KeyCode::F5 {
+			match self.world.save(SNAPSHOT_PATH) {
+				Ok(()) => println!("saved to {}", SNAPSHOT_PATH),
+				Err(e) => println!("failed to save: {}", e),
+			}
+		}
+		if let Some(player) = self.player.filter(|id| self.world.objects.contains_key(id)) {
+			self.player_key(player, keycode);
+		}
 		self.controls.upd_key(keycode, true);
 	}
 
@@ -120,6 +162,8 @@ impl EventHandler for WorldWithDebugInfo {
 
 	fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
 		self.controls.mouse = Coords{ x, y };
+		let cursor = CircleBounds { coords : self.controls.mouse, r : 0.1 };
+		self.selected = self.world.near(&cursor).find(|obj| obj.bounds.collides_with(&cursor)).map(|obj| obj.id);
 	}
 }
 
@@ -142,9 +186,11 @@ impl EventHandler for World {
 }
 
 pub fn ggez_loop(w : World) {
-	let cb = ggez::ContextBuilder::new("super_simple", "ggez");
+	let cb = ggez::ContextBuilder::new("super_simple", "ggez").window_mode(WindowMode::default().dimensions(w.size.x, w.size.y));
 	let (ctx, event_loop) = &mut cb.build().unwrap();
-	run(ctx, event_loop, &mut WorldWithDebugInfo { world : w, fps : fps_counter::FPSCounter::new(), controls: ControlsState::init() }).unwrap();
+	let player = w.objects.values().find(|item| item.blueprint.name == "Player").map(|player| player.id);
+	let controls = ControlsState::init();
+	run(ctx, event_loop, &mut WorldWithDebugInfo { world : w, fps : fps_counter::FPSCounter::new(), controls, player, selected : None }).unwrap();
 }
 
 pub fn point2(coords : &Coords) -> Point2<f32> {
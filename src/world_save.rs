@@ -0,0 +1,67 @@
+use crate::world_gen::World;
+use crate::world_update::GameObj;
+use crate::world_update::FrameCount;
+use crate::world_update::ObjId;
+use crate::collision::{Size, Dist};
+use crate::soil::Soil;
+use rand_pcg::Pcg32;
+use serde::{Serialize, Deserialize};
+use std::fs::File;
+use std::io;
+
+// saved as WorldSnapshot<&GameObj>, loaded as WorldSnapshot<GameObj>
+#[derive(Serialize, Deserialize)]
+struct WorldSnapshot<O> {
+	size : Size,
+	cell_size : Dist,
+	time : FrameCount,
+	next_id : ObjId,
+	rng : Pcg32,
+	soil : Soil,
+	objects : Vec<O>
+}
+
+impl World {
+	pub fn save(&self, path : &str) -> io::Result<()> {
+		let snapshot = WorldSnapshot {
+			size : self.size,
+			cell_size : self.cell_size(),
+			time : self.time,
+			next_id : self.next_id(),
+			rng : self.rng_state(),
+			soil : self.soil.clone(),
+			objects : self.objects.values().collect(),
+		};
+		serde_json::to_writer(File::create(path)?, &snapshot)?;
+		Ok(())
+	}
+
+	pub fn load(path : &str) -> io::Result<World> {
+		let s : WorldSnapshot<GameObj> = serde_json::from_reader(File::open(path)?)?;
+		Ok(World::restore(s.size, s.cell_size, s.time, s.next_id, s.rng, s.soil, s.objects))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::world_gen::{World, generate_world};
+	use crate::collision::{Point, GRID_CELL_SIZE};
+	use crate::game_data::load_for_tests;
+	use std::fs;
+
+	#[test]
+	fn a_loaded_world_goes_on_exactly_like_the_saved_one() {
+		load_for_tests();
+		let path = |name : &str| std::env::temp_dir().join(format!("wuxia_clan_war_{}_{}.json", std::process::id(), name)).to_str().unwrap().to_string();
+		let mut world = generate_world(Point::new(300.0, 300.0), GRID_CELL_SIZE, 7);
+		for _ in 0..100 { world.upd(); }
+		world.save(&path("saved")).unwrap();
+		let mut loaded = World::load(&path("saved")).unwrap();
+		for _ in 0..100 { world.upd(); loaded.upd(); }
+		world.save(&path("continued")).unwrap();
+		loaded.save(&path("loaded")).unwrap();
+		let (continued, loaded) = (fs::read(path("continued")).unwrap(), fs::read(path("loaded")).unwrap());
+		["saved", "continued", "loaded"].iter().for_each(|name| { fs::remove_file(path(name)).ok(); });
+		assert!(continued == loaded);
+	}
+}
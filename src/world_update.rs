@@ -1,4 +1,5 @@
 use crate::collision::move_to_target;
+use crate::collision::intercept;
 use crate::world_gen::World;
 use crate::collision::Point;
 use crate::colors::ColorTone;
@@ -9,162 +10,642 @@ use crate::world_gen::gen_circle_bounds;
 use ordered_float::OrderedFloat;
 use self::TaskUpd::*;
 use std::collections::HashSet;
-use itertools::Itertools;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use crate::collision::RectBounds;
-use crate::std_extended::rng_range;
 use crate::collision::Dist;
+use serde::{Serialize, Deserialize, Serializer, Deserializer};
+use crate::game_data::GAME_DATA;
+use crate::game_data::ClanId;
+use crate::techniques::{Technique, TechniqueId};
+use crate::collision::Direction;
 
 pub type FrameCount = u64;
-const FRAME_MOMENT : FrameCount = 60;
-const FRAME_HOUR : FrameCount = 60 * FRAME_MOMENT;
+pub type ObjId = u64;
+pub type BlueprintId = usize; // index in GameData::blueprints
+pub const FRAME_MOMENT : FrameCount = 60;
 const FRAME_DAY : FrameCount = FRAME_MOMENT * 24;
 const FRAME_YEAR : FrameCount = FRAME_DAY * 365;
+const FRAME_FOREVER : FrameCount = FrameCount::max_value();
 
-#[derive(Debug)]
+// data files measure time in days, or in moments for anything short
+pub fn moments<'de, D : Deserializer<'de>>(d : D) -> Result<FrameCount, D::Error> {
+	f64::deserialize(d).map(|moments| (moments * FRAME_MOMENT as f64).round() as FrameCount)
+}
+
+// for what's both in data files and snapshots
+fn serialize_moments<S : Serializer>(frames : &FrameCount, s : S) -> Result<S::Ok, S::Error> {
+	s.serialize_f64(*frames as f64 / FRAME_MOMENT as f64)
+}
+
+fn days<'de, D : Deserializer<'de>>(d : D) -> Result<FrameCount, D::Error> {
+	f64::deserialize(d).map(|days| (days * FRAME_DAY as f64) as FrameCount)
+}
+
+fn optional_days<'de, D : Deserializer<'de>>(d : D) -> Result<Option<FrameCount>, D::Error> {
+	Option::<f64>::deserialize(d).map(|days| days.map(|days| (days * FRAME_DAY as f64) as FrameCount))
+}
+
+// 0 at midnight, 1 at noon
+pub fn daylight(time : FrameCount) -> Amount {
+	let day = (time % FRAME_DAY) as Amount / FRAME_DAY as Amount;
+	0.5 - 0.5 * (2.0 * std::f32::consts::PI * day).cos()
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Deserialize)]
+pub enum Season { Spring = 0, Summer = 1, Autumn = 2, Winter = 3 }
+
+pub fn season(time : FrameCount) -> Season {
+	match time % FRAME_YEAR * 4 / FRAME_YEAR {
+		0 => Season::Spring,
+		1 => Season::Summer,
+		2 => Season::Autumn,
+		_ => Season::Winter
+	}
+}
+
+pub fn format_time(time : FrameCount) -> String {
+	format!("{} years, {} days, {} moments, {:?}", time / FRAME_YEAR, time % FRAME_YEAR / FRAME_DAY, time % FRAME_DAY / FRAME_MOMENT, season(time))
+}
+
+const MAX_SATIETY : Amount = 100.0;
+const WELL_FED : Amount = MAX_SATIETY * 0.7;
+const SATED : Amount = MAX_SATIETY * 0.9; // foraging stops here
+
+pub const REALMS : &[&str] = &["Mortal", "Qi Condensation", "Foundation Establishment", "Core Formation", "Nascent Soul"];
+const REALM_BONUS : Amount = 0.25; // speed, durability and damage gained with every realm
+const REALM_QI : Amount = 100.0; // qi needed to attempt a breakthrough from the first realm, doubles with each realm
+const BREAKTHROUGH_CHANCE : Amount = 0.6; // divided by the number of the realm being attempted
+pub const MEDITATION : FrameCount = FRAME_DAY / 2;
+
+const SCREAM_RANGE : Dist = 60.0;
+const FLEE_TIME : FrameCount = FRAME_MOMENT * 3;
+const SCREAM_COOLDOWN : FrameCount = FRAME_MOMENT * 10;
+const CHASE_TIME : FrameCount = FRAME_MOMENT * 10;
+const GIVE_UP_TIME : FrameCount = CHASE_TIME * 3; // an escaped prey isn't chased again for that long
+const FOLLOW_DIST : Dist = 30.0; // how far pack members let their leader go
+const FLANK_DIST : Dist = 25.0; // pack members circle the prey at this distance before closing in
+
+const NIGHT_SIGHT : Amount = 0.4; // share of perception left at midnight, except for the nocturnal
+const SLEEP_LIGHT : Amount = 0.25; // diurnal sleep while it's darker than that, nocturnal while it's as much brighter
+
+const YOUNG_VIGOR : Amount = 0.5; // share of adult durability and speed at birth
+const OLD_AGE : Amount = 0.5; // share of max_lifetime after which objects start to weaken
+const ELDER_VIGOR : Amount = 0.5; // share of adult durability and speed left at max_lifetime
+const GROWTH_STEP : Dist = 0.1; // radius changes smaller than that wait, so the grid isn't updated every tick
+
+// per season, indexed by Season
+const PLANT_SPREAD : [Amount; 4] = [0.05, 0.03, 0.01, 0.0]; // chance for a plant to spawn offspring every moment
+const COLD_HUNGER : [Amount; 4] = [1.0, 1.0, 1.25, 1.5]; // hunger multiplier
+
+// soil fertility goes from 0 to 1
+const SOIL_REGROWTH : Amount = 0.02; // every moment
+const PLANT_DRAIN : Amount = 0.001; // every moment per squared unit of plant radius
+const GRAZING : Amount = 0.05; // when a plant is eaten
+const ROT_FERTILITY : Amount = 0.01; // per durability a carcass loses to decay
+const BARREN : Amount = 0.2; // plants don't spread to soil poorer than that
+
+const CROWD_RANGE : Dist = 20.0; // plants this close to each other make up a patch
+const CROWDED : usize = 12; // plants don't spread where that many others of their kind already grow within CROWD_RANGE
+
+// how many times losers of a contested action may re-plan within one tick
+const MAX_REPLANS : usize = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct GameObj {
+	pub id : ObjId, // assigned by World::insert
+	#[serde(with = "blueprint_name")]
 	pub blueprint : &'static GameObjBlueprint,
 	pub bounds : CircleBounds,
+	pub velocity : Direction, // last tick's movement, set by World::move_obj
 	pub durability : Amount,
+	pub satiety : Amount,
+	pub born : FrameCount,
+	pub starting_age : FrameCount, // age it had when it appeared, for those placed by worldgen
+	#[serde(with = "clan_name")]
+	pub clan : Option<ClanId>,
+	pub qi : Amount,
+	pub realm : usize, // index in REALMS
+	pub leader : Option<ObjId>, // pack leader, might be gone already
+	pub inventory : Vec<Item>, // at most blueprint.capacity
 	pub tasks : Vec<Task>,
 	pub tmp_effects : Vec<TemporalEffect>,
 }
 
-#[derive(Debug)]
+// a picked up object, turns back into one when dropped
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Item {
+	#[serde(with = "blueprint_name")]
+	pub blueprint : &'static GameObjBlueprint,
+	pub durability : Amount,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct TemporalEffect { start : FrameCount, duration : FrameCount, effect : EffectType } // TODO: curse, buffs/debuffs
 
 impl TemporalEffect {
-	fn new(start : FrameCount, duration : FrameCount, effect : EffectType) -> TemporalEffect {
+	pub fn new(start : FrameCount, duration : FrameCount, effect : EffectType) -> TemporalEffect {
 		TemporalEffect { start, duration, effect }
 	}
 }
 
-#[derive(Debug, Eq, PartialEq)]
-enum EffectType { PeriodicEffect { period : FrameCount, effect : PeriodicEffectType }, Slow(OrderedFloat<Amount>), Stun, DelayedDeath }
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EffectType {
+	PeriodicEffect { #[serde(serialize_with = "serialize_moments", deserialize_with = "moments")] period : FrameCount, effect : PeriodicEffectType },
+	Slow(OrderedFloat<Amount>),
+	Stun,
+	DelayedDeath,
+	Gestation,
+	Cooldown(#[serde(with = "technique_name")] TechniqueId),
+	GaveUp(ObjId), // on a prey that escaped a chase
+	Screamed, // keeps the alarm from being raised every tick
+}
 
-#[derive(Debug, Eq, PartialEq)]
-enum PeriodicEffectType { Hunger(OrderedFloat<Amount>), Damage(OrderedFloat<Amount>) }
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PeriodicEffectType { Hunger(OrderedFloat<Amount>), Damage(OrderedFloat<Amount>) }
 
 impl GameObj {
-	pub fn from(blueprint : &'static GameObjBlueprint, bounds : CircleBounds, time : FrameCount) -> GameObj {
+	pub fn from(blueprint : &'static GameObjBlueprint, bounds : CircleBounds, w : &World) -> GameObj {
+		let time = w.time;
 		let tmp_effects = blueprint.max_lifetime.iter().map( |lifetime| TemporalEffect {
-			start: time + (*lifetime as f32 * rng_range(&(0.7..1.0))) as u64,
+			start: time + (*lifetime as f32 * w.rng_range(&(0.7..1.0))) as u64,
 			duration: 1,
 			effect: EffectType::DelayedDeath
-		}).collect();
-		GameObj { blueprint, durability : blueprint.durability, bounds, tasks : blueprint.tasks.to_vec(), tmp_effects }
+		}).chain(blueprint.hunger.iter().map( |drain| TemporalEffect::new(
+			time,
+			FRAME_FOREVER,
+			EffectType::PeriodicEffect { period : FRAME_MOMENT, effect : PeriodicEffectType::Hunger(OrderedFloat(*drain)) }
+		))).chain(blueprint.decay.iter().map( |rot| TemporalEffect::new(
+			time,
+			FRAME_FOREVER,
+			EffectType::PeriodicEffect { period : FRAME_MOMENT, effect : PeriodicEffectType::Damage(OrderedFloat(*rot)) }
+		))).collect();
+		let mut obj = GameObj {
+			id : 0,
+			blueprint,
+			durability : blueprint.durability,
+			satiety : MAX_SATIETY,
+			born : time,
+			starting_age : 0,
+			clan : None,
+			qi : 0.0,
+			realm : 0,
+			leader : None,
+			inventory : vec![],
+			bounds,
+			velocity : Point::init(),
+			tasks : blueprint.tasks.to_vec(),
+			tmp_effects
+		};
+		obj.bounds.r = obj.grown_radius(time);
+		obj.durability *= obj.vigor(time);
+		obj
+	}
+
+	// worldgen rolls ages short of death or up to twice the maturity, so a population doesn't grow old and die all at once
+	pub fn with_random_age(blueprint : &'static GameObjBlueprint, bounds : CircleBounds, w : &World) -> GameObj {
+		let (mut obj, time) = (GameObj::from(blueprint, bounds, w), w.time);
+		let death = obj.tmp_effects.iter().find(|eff| eff.effect == EffectType::DelayedDeath).map(|eff| eff.start - time);
+		let age = w.rng_range(&(0.0..death.unwrap_or(blueprint.maturity * 2) as Dist)) as FrameCount;
+		obj.starting_age = age;
+		obj.tmp_effects.iter_mut().filter(|eff| eff.effect == EffectType::DelayedDeath).for_each(|eff| eff.start -= age);
+		obj.bounds.r = obj.grown_radius(time);
+		obj.durability = blueprint.durability * obj.vigor(time);
+		obj
+	}
+
+	fn carrion(&self) -> bool {
+		match self.blueprint.genus { Genus::Carrion(_) => true, _ => false }
+	}
+
+	// a carcass is worth whatever is left of it
+	fn nutrition(&self) -> Amount {
+		if self.carrion() { self.durability.max(0.0) } else { self.blueprint.nutrition }
+	}
+
+	fn age(&self, time : FrameCount) -> FrameCount { time.saturating_sub(self.born) + self.starting_age }
+
+	// 0 at birth, 1 once mature
+	fn growth(&self, time : FrameCount) -> Amount {
+		if self.blueprint.maturity == 0 { 1.0 } else { (self.age(time) as Amount / self.blueprint.maturity as Amount).min(1.0) }
+	}
+
+	// young objects grow from the lower radius bound to the upper one
+	fn grown_radius(&self, time : FrameCount) -> Dist {
+		self.blueprint.radius.start + (self.blueprint.radius.end - self.blueprint.radius.start) * self.growth(time)
+	}
+
+	// share of an adult's durability and speed: rises while growing up, falls in old age
+	fn vigor(&self, time : FrameCount) -> Amount {
+		let youth = YOUNG_VIGOR + (1.0 - YOUNG_VIGOR) * self.growth(time);
+		let decline = self.blueprint.max_lifetime.map(|lifetime| {
+			((self.age(time) as Amount / lifetime as Amount - OLD_AGE) / (1.0 - OLD_AGE)).max(0.0).min(1.0)
+		}).unwrap_or(0.0);
+		youth * (1.0 - (1.0 - ELDER_VIGOR) * decline)
+	}
+
+	fn active_effects(&self, time : FrameCount) -> impl Iterator<Item=&EffectType> {
+		self.tmp_effects.iter().filter(move |eff| eff.start <= time && time <= eff.start.saturating_add(eff.duration)).
+			map(|eff| &eff.effect)
+	}
+
+	pub fn stunned(&self, time : FrameCount) -> bool {
+		self.active_effects(time).any(|eff| *eff == EffectType::Stun)
+	}
+
+	pub fn realm_factor(&self) -> Amount { 1.0 + REALM_BONUS * self.realm as Amount }
+
+	pub fn speed(&self, time : FrameCount) -> Dist {
+		if self.stunned(time) { return 0.0 }
+		self.active_effects(time).fold(self.blueprint.speed * self.realm_factor() * self.vigor(time), |speed, eff| match eff {
+			EffectType::Slow(OrderedFloat(factor)) => speed * factor,
+			_ => speed
+		})
+	}
+
+	fn pregnant(&self) -> bool {
+		self.tmp_effects.iter().any(|eff| eff.effect == EffectType::Gestation)
+	}
+
+	// the gestation effect starts once the offspring is due and lasts until it's born
+	fn due_birth(&self, time : FrameCount) -> bool {
+		self.tmp_effects.iter().any(|eff| eff.effect == EffectType::Gestation && eff.start <= time)
+	}
+
+	fn fertile(&self, time : FrameCount) -> bool {
+		self.age(time) >= self.blueprint.maturity && self.satiety >= WELL_FED && !self.pregnant() &&
+			(self.blueprint.breeding.is_empty() || self.blueprint.breeding.contains(&season(time)))
+	}
+
+	fn perception(&self, time : FrameCount) -> Dist {
+		match self.blueprint.activity {
+			Activity::Nocturnal => self.blueprint.perception,
+			_ => self.blueprint.perception * (NIGHT_SIGHT + (1.0 - NIGHT_SIGHT) * daylight(time))
+		}
+	}
+
+	fn asleep(&self, time : FrameCount) -> bool {
+		match self.blueprint.activity {
+			Activity::Always => false,
+			Activity::Diurnal => daylight(time) < SLEEP_LIGHT,
+			Activity::Nocturnal => daylight(time) > 1.0 - SLEEP_LIGHT,
+		}
+	}
+
+	// the nearest object matching `pred` within sight
+	fn perceive<'a, F>(&self, w : &'a World, pred : F) -> Option<&'a GameObj> where F : Fn(&GameObj) -> bool {
+		w.nearest(&self.bounds.coords, self.perception(w.time), pred)
+	}
+
+	// a random spot within sight, for when there's nothing around to go after
+	fn roam(&self, w : &World) -> TaskUpd {
+		gen_circle_bounds(w, Some(&CircleBounds { r : self.perception(w.time), ..self.bounds }), &self.blueprint).
+			map(|b| TaskPush(Task::GetTo(CircleBounds { coords : w.clamp(&b.coords), ..b }))).unwrap_or(TaskWait)
+	}
+
+	fn leader<'a>(&self, w : &'a World) -> Option<&'a GameObj> {
+		self.leader.and_then(|id| w.objects.get(&id))
+	}
+
+	// members share kills, a pack is named after whoever leads it
+	fn pack(&self) -> Option<ObjId> {
+		if self.blueprint.pack { Some(self.leader.unwrap_or(self.id)) } else { None }
+	}
+
+	// the lowest id in a group leads, so nobody ends up following its own follower
+	fn find_leader<'a>(&self, w : &'a World) -> Option<&'a GameObj> {
+		if !self.blueprint.pack { return None }
+		self.perceive(w, |obj| obj.id < self.id && obj.blueprint.name == self.blueprint.name && obj.leader(w).is_none())
+	}
+
+	// place around the prey for a pack member: position among the leader's followers and the pack size
+	fn pack_slot(&self, w : &World) -> Option<(usize, usize)> {
+		self.leader(w).map(|leader| {
+			let members : Vec<ObjId> = w.near(&CircleBounds { r : leader.blueprint.perception, ..leader.bounds }).
+				filter(|obj| obj.leader == Some(leader.id)).map(|obj| obj.id).collect();
+			(members.iter().position(|id| *id == self.id).unwrap_or(0) + 1, members.len() + 1)
+		})
+	}
+
+	// pack members spread out around the prey and close in once they're all around it
+	fn flank(&self, w : &World, prey : &GameObj, target : Point) -> Point {
+		match (self.leader(w), self.pack_slot(w)) {
+			(Some(leader), Some((slot, size))) if self.bounds.coords.dist(&prey.bounds.coords) > FLANK_DIST => {
+				let side = leader.bounds.coords - prey.bounds.coords;
+				let angle = side.y.atan2(side.x) + 2.0 * std::f32::consts::PI * slot as f32 / size as f32;
+				target + Point::new(angle.cos(), angle.sin()).multf(FLANK_DIST)
+			},
+			_ => target
+		}
+	}
+
+	fn gave_up_on(&self, prey : ObjId, time : FrameCount) -> bool {
+		self.active_effects(time).any(|eff| *eff == EffectType::GaveUp(prey))
+	}
+
+	fn find_mate<'a>(&self, w : &'a World) -> Option<&'a GameObj> {
+		self.perceive(w, |obj| obj.id != self.id && obj.blueprint.name == self.blueprint.name && obj.fertile(w.time))
+	}
+
+	fn wants_to_mate(&self, w : &World) -> bool {
+		self.fertile(w.time) && self.find_mate(w).is_some()
+	}
+
+	fn plant(&self) -> bool {
+		match self.blueprint.genus { Genus::Plant(_) => true, _ => false }
+	}
+
+	fn animal(&self) -> bool {
+		match self.blueprint.genus { Genus::Animal(_, _) => true, _ => false }
+	}
+
+	fn offspring_area(&self) -> CircleBounds { CircleBounds { r: self.bounds.r * 4.0, ..self.bounds } }
+
+	// at least `count` others of its kind within CROWD_RANGE
+	fn kin_around(&self, w : &World, count : usize) -> bool {
+		let area = CircleBounds { r : CROWD_RANGE, ..self.bounds };
+		w.near(&area).filter(|obj| obj.id != self.id && obj.blueprint.name == self.blueprint.name && obj.bounds.collides_with(&area)).nth(count - 1).is_some()
+	}
+
+	fn crowded(&self, w : &World) -> bool { self.kin_around(w, CROWDED) }
+
+	// gatherers leave the last plant of a patch alone, so it grows back
+	fn gatherable(&self, w : &World) -> bool {
+		self.blueprint.portable && (!self.plant() || self.kin_around(w, 1))
+	}
+
+	fn spawn_offspring(&self, w : &World) -> TaskUpd {
+		let new_b = &self.offspring_area();
+		gen_circle_bounds(w, Some(&new_b), &self.blueprint).
+			filter(|b| w.contains(&b.coords) && (!self.plant() || w.soil.fertility(&b.coords) >= BARREN)).map(|b| {
+			let mut child = GameObj::from(&self.blueprint, b, w);
+			child.clan = self.clan;
+			TaskAct(Action::Spawn(child))
+		}).unwrap_or(TaskWait)
+	}
+
+	fn hostile_to(&self, other : &GameObj) -> bool {
+		match (self.clan, other.clan) {
+			(Some(a), Some(b)) => GAME_DATA.hostile(a, b),
+			_ => false
+		}
+	}
+
+	fn hit(&self, victim : &GameObj) -> TaskUpd {
+		TaskAct(Action::Hit { target : victim.id, damage : self.blueprint.damage * self.realm_factor() })
+	}
+
+	pub fn technique(&self, slot : usize) -> Option<TechniqueId> {
+		self.blueprint.techniques.get(slot).and_then(|name| Technique::by_name(name))
+	}
+
+	pub fn ready(&self, technique : TechniqueId, time : FrameCount) -> bool {
+		self.qi >= GAME_DATA.techniques[technique].qi_cost && !self.active_effects(time).any(|eff| *eff == EffectType::Cooldown(technique))
+	}
+
+	// the most damaging ready technique that would reach the target
+	fn choose_technique(&self, target : &GameObj, time : FrameCount) -> Option<TechniqueId> {
+		let aim = target.bounds.coords - self.bounds.coords;
+		(0..self.blueprint.techniques.len()).filter_map(|slot| self.technique(slot)).
+			filter(|id| self.ready(*id, time) && GAME_DATA.techniques[*id].shape.covers(&self.bounds.coords, &aim, &target.bounds)).
+			max_by_key(|id| OrderedFloat(GAME_DATA.techniques[*id].damage))
+	}
+
+	// herbivores something hunts run from predators and alarms, unless someone steers them by hand
+	fn timid(&self) -> bool {
+		match (&self.blueprint.genus, self.tasks.first()) {
+			(_, Some(Task::ListenControls)) => false,
+			(genus @ Genus::Animal(_, FoodPreference::Herbivore), _) => GAME_DATA.blueprints.iter().any(|b| b.hunts(genus)),
+			_ => false
+		}
+	}
+
+	fn fleeing(&self) -> bool {
+		match self.tasks.last() { Some(Task::Flee { .. }) => true, _ => false }
+	}
+
+	// only the timid who aren't running yet look out for predators
+	fn spot_predator<'a>(&self, w : &'a World) -> Option<&'a GameObj> {
+		if !self.timid() || self.fleeing() { return None }
+		self.perceive(w, |obj| obj.blueprint.hunts(&self.blueprint.genus))
+	}
+
+	fn screamed(&self, time : FrameCount) -> bool {
+		self.active_effects(time).any(|eff| *eff == EffectType::Screamed)
+	}
+
+	fn hear_alarm(&mut self, threat : Point, time : FrameCount) {
+		if self.timid() && !self.fleeing() { self.tasks.push(Task::Flee { from : threat, until : time + FLEE_TIME }) }
+	}
+
+	fn can_meditate(&self) -> bool {
+		self.blueprint.qi_gain.is_some()
+	}
+
+	fn breakthrough_qi(&self) -> Amount { REALM_QI * (2.0 as Amount).powi(self.realm as i32) }
+
+	// a moment of meditation, `roll` is uniform in 0..1, a failed breakthrough means qi deviation
+	fn meditate(&mut self, time : FrameCount, roll : Amount) {
+		self.qi = (self.qi + self.blueprint.qi_gain.unwrap_or(0.0)).min(self.breakthrough_qi());
+		if self.qi < self.breakthrough_qi() || self.realm + 1 >= REALMS.len() { return }
+		if roll < BREAKTHROUGH_CHANCE / (self.realm + 1) as Amount {
+			self.realm += 1;
+			self.qi = 0.0;
+			self.durability += self.blueprint.durability * REALM_BONUS;
+		} else {
+			self.qi *= 0.5;
+			self.tmp_effects.push(TemporalEffect::new(time, FRAME_MOMENT * 5, EffectType::Stun));
+			self.tmp_effects.push(TemporalEffect::new(time, FRAME_MOMENT * 5, EffectType::PeriodicEffect {
+				period : FRAME_MOMENT,
+				effect : PeriodicEffectType::Damage(OrderedFloat(self.blueprint.durability * 0.1))
+			}));
+		}
+	}
+
+	pub fn has_room(&self) -> bool { self.inventory.len() < self.blueprint.capacity }
+
+	fn max_durability(&self, time : FrameCount) -> Amount { self.blueprint.durability * self.realm_factor() * self.vigor(time) }
+
+	fn wounded(&self, time : FrameCount) -> bool { self.durability < self.max_durability(time) * 0.5 }
+
+	// the most nourishing item, if any is edible at all
+	fn meal(&self) -> Option<usize> {
+		(0..self.inventory.len()).filter(|slot| self.inventory[*slot].blueprint.nutrition > 0.0).
+			max_by_key(|slot| OrderedFloat(self.inventory[*slot].blueprint.nutrition))
+	}
+
+	// the item that heals the most, if any heals at all
+	fn remedy(&self) -> Option<usize> {
+		(0..self.inventory.len()).filter(|slot| self.inventory[*slot].blueprint.healing > 0.0).
+			max_by_key(|slot| OrderedFloat(self.inventory[*slot].blueprint.healing))
+	}
+
+	fn heal(&mut self, healing : Amount, time : FrameCount) {
+		self.durability = (self.durability + healing).min(self.max_durability(time).max(self.durability));
+	}
+
+	fn feed(&mut self, nutrition : Amount) {
+		self.satiety = (self.satiety + nutrition).min(MAX_SATIETY);
+	}
+
+	// starvation eats into durability once satiety runs out
+	fn starve(&mut self, drain : Amount) {
+		self.satiety -= drain;
+		if self.satiety < 0.0 {
+			self.durability += self.satiety;
+			self.satiety = 0.0;
+		}
 	}
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct GameObjBlueprint {
-	pub name : &'static str,
+	pub name : String,
 	pub genus : Genus,
 	pub min_dist : Dist, // required for worldgen
 	pub radius : Range<Dist>,
 	pub color : ColorTone,
 	pub durability : Amount,
 	pub speed : Dist,
-	pub tasks : &'static [Task],
+	pub perception : Dist, // how far it notices food, mates, enemies and predators
+	#[serde(default)]
+	pub activity : Activity,
+	pub nutrition : Amount, // satiety gained by the one who swallows it
+	#[serde(default)]
+	pub healing : Amount, // durability restored by eating it from an inventory
+	#[serde(default)]
+	pub portable : bool, // can be picked up
+	#[serde(default)]
+	pub capacity : usize, // inventory size
+	#[serde(default)]
+	pub damage : Amount, // dealt by a single hit
+	#[serde(default)]
+	pub qi_gain : Option<Amount>, // qi gathered every moment of meditation, if it can cultivate at all
+	#[serde(default)]
+	pub hunger : Option<Amount>, // satiety lost every moment
+	#[serde(default)]
+	pub forage : Option<Genus>, // eaten when hungry with nothing edible at hand, for those whose tasks aren't about food
+	#[serde(default)]
+	pub decay : Option<Amount>, // durability lost every moment
+	#[serde(default)]
+	pub remains : Vec<String>, // blueprints left behind on death, unless it was eaten
+	#[serde(skip)]
+	pub remains_ids : Vec<BlueprintId>, // resolved by GameData::validate
+	#[serde(default, deserialize_with = "days")]
+	pub maturity : FrameCount, // age required for mating
+	#[serde(default, deserialize_with = "days")]
+	pub gestation : FrameCount,
+	#[serde(default)]
+	pub breeding : Vec<Season>, // seasons it mates in, any if empty
+	#[serde(default)]
+	pub pack : bool, // hunts together with others of its kind
+	#[serde(default)]
+	pub techniques : Vec<String>, // names in GameData::techniques, the player triggers them by slot
+	pub tasks : Vec<Task>,
+	#[serde(default, deserialize_with = "optional_days")]
 	pub max_lifetime: Option<FrameCount>
 }
 
+// snapshots refer to blueprints by name
+mod blueprint_name {
+	use super::GameObjBlueprint;
+	use serde::{Serializer, Deserializer, Deserialize};
+	use serde::de::Error;
+
+	pub fn serialize<S : Serializer>(blueprint : &&'static GameObjBlueprint, s : S) -> Result<S::Ok, S::Error> {
+		s.serialize_str(&blueprint.name)
+	}
+
+	pub fn deserialize<'de, D : Deserializer<'de>>(d : D) -> Result<&'static GameObjBlueprint, D::Error> {
+		let name = String::deserialize(d)?;
+		GameObjBlueprint::by_name(&name).ok_or_else(|| D::Error::custom(format!("unknown blueprint: {}", name)))
+	}
+}
+
+mod clan_name {
+	use crate::game_data::{GAME_DATA, ClanId};
+	use serde::{Serializer, Deserializer, Serialize, Deserialize};
+	use serde::de::Error;
+
+	pub fn serialize<S : Serializer>(clan : &Option<ClanId>, s : S) -> Result<S::Ok, S::Error> {
+		clan.map(|clan| &GAME_DATA.clans[clan].name).serialize(s)
+	}
+
+	pub fn deserialize<'de, D : Deserializer<'de>>(d : D) -> Result<Option<ClanId>, D::Error> {
+		Option::<String>::deserialize(d)?.map(|name| {
+			GAME_DATA.clan_by_name(&name).ok_or_else(|| D::Error::custom(format!("unknown clan: {}", name)))
+		}).transpose()
+	}
+}
+
+mod technique_name {
+	use crate::game_data::GAME_DATA;
+	use crate::techniques::{Technique, TechniqueId};
+	use serde::{Serializer, Deserializer, Deserialize};
+	use serde::de::Error;
+
+	pub fn serialize<S : Serializer>(technique : &TechniqueId, s : S) -> Result<S::Ok, S::Error> {
+		s.serialize_str(&GAME_DATA.techniques[*technique].name)
+	}
+
+	pub fn deserialize<'de, D : Deserializer<'de>>(d : D) -> Result<TechniqueId, D::Error> {
+		let name = String::deserialize(d)?;
+		Technique::by_name(&name).ok_or_else(|| D::Error::custom(format!("unknown technique: {}", name)))
+	}
+}
+
 impl GameObjBlueprint {
-	pub const TREE: GameObjBlueprint = GameObjBlueprint {
-		name : "Tree",
-		genus : Genus::Plant(Size::Big),
-		min_dist : 10.0,
-		radius: (8.0..18.0),
-		color : ColorTone::Brown,
-		durability : 100.0,
-		speed : 0.0,
-		tasks : &[],
-		max_lifetime: None
-	};
-
-	pub const WANDERER: GameObjBlueprint = GameObjBlueprint {
-		name : "Wanderer",
-		genus : Genus::Animal(Size::Average, FoodPreference::Herbivore),
-		min_dist : 2.0,
-		radius: (4.0..4.0),
-		color : ColorTone::Black,
-		durability : 20.0,
-		speed : 0.33,
-		tasks : &[Task::Wander()],
-		max_lifetime: Some(2 * FRAME_YEAR)
-	};
-
-	pub const PLAYER: GameObjBlueprint = GameObjBlueprint {
-		name : "Player",
-		genus : Genus::Animal(Size::Average, FoodPreference::Herbivore),
-		min_dist : 2.0,
-		radius: (4.0..4.0),
-		color : ColorTone::BlueViolet,
-		durability : 20.0,
-		speed : 0.33,
-		tasks : &[Task::ListenControls()],
-		max_lifetime: Some(2 * FRAME_YEAR)
-	};
-
-	pub const HARE : GameObjBlueprint = GameObjBlueprint {
-		name : "Hare",
-		genus : Genus::Animal(Size::Small, FoodPreference::Herbivore),
-		min_dist : 2.0,
-		radius: (3.0..3.0),
-		color : ColorTone::White,
-		durability : 15.0,
-		speed : 0.9,
-		tasks : &[Task::Eat(Genus::Plant(Size::Small))],
-		max_lifetime: Some(FRAME_YEAR)
-	};
-
-	pub const GRASS: GameObjBlueprint = GameObjBlueprint {
-		name : "Grass",
-		genus : Genus::Plant(Size::Small),
-		min_dist : 0.0,
-		radius: (2.0..2.0),
-		color : ColorTone::DarkGreen,
-		durability : 20.0,
-		speed : 0.0,
-		tasks : &[Task::Reproduce],
-		max_lifetime: Some(FRAME_DAY)
-	};
-
-	pub const WOLF: GameObjBlueprint = GameObjBlueprint {
-		name : "Wolf",
-		genus : Genus::Animal(Size::Average, FoodPreference::Carnivore),
-		min_dist : 10.0,
-		radius: (6.0..6.0),
-		color : ColorTone::Red,
-		durability : 100.0,
-		speed : 0.6,
-		tasks : &[Task::Hunt(Genus::Animal(Size::Small, FoodPreference::Herbivore))],
-		max_lifetime: Some(FRAME_YEAR)
-	};
+	pub fn by_name(name : &str) -> Option<&'static GameObjBlueprint> {
+		GAME_DATA.blueprints.iter().find(|blueprint| blueprint.name == name)
+	}
+
+	fn hunts(&self, genus : &Genus) -> bool {
+		self.tasks.iter().any(|task| match task { Task::Hunt(prey) => prey == genus, _ => false })
+	}
 }
 
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum Genus { Plant(Size), Animal(Size, FoodPreference) }
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+pub enum Activity { Always, Diurnal, Nocturnal }
+
+impl Default for Activity {
+	fn default() -> Activity { Activity::Always }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Genus { Plant(Size), Animal(Size, FoodPreference), Carrion(Size) }
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Size { Small, Average, Big }
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum FoodPreference { Herbivore, Carnivore }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Task {
-	Wander(),
+	Wander,
 	GetTo(CircleBounds),
 	Eat(Genus),
 	Hunt(Genus),
+	Chase { prey : ObjId, until : FrameCount },
 	Reproduce,
-	ListenControls(),
+	Gather, // pick up portable objects in sight until the inventory is full
+	Feud, // attack members of hostile clans, otherwise mate, heal, eat, meditate, gather or wander
+	Meditate { until : FrameCount },
+	Flee { from : Point, until : FrameCount },
+	ListenControls,
 }
 
 pub enum Action {
-	Scream { bounds : CircleBounds },
-	Hit { bounds : CircleBounds, damage : f32 },
-	Swallow(usize),
-	Pick(usize),
+	Scream { bounds : CircleBounds, threat : Point }, // everyone in the bounds hears about the threat
+	Hit { target : ObjId, damage : f32 },
+	Strike { technique : TechniqueId, aim : Direction },
+	Swallow(ObjId),
+	Pick(ObjId),
+	EatItem(usize), // inventory slot
+	Mate(ObjId),
+	Follow(ObjId), // join a pack
+	GiveUp(ObjId), // stop chasing a prey and leave it be for a while
+	Meditate,
 	MoveTo(Point),
 	Spawn(GameObj)
 }
@@ -178,57 +659,146 @@ pub enum TaskUpd {
 
 impl GameObj {
 	pub fn move_to(&self, w : &World, target : &Point) -> Point {
-		let mut obstacles = w.objects.iter().filter(|o| o.blueprint.genus != Genus::Plant(Size::Small)).map(|o| &o.bounds);
-		self.bounds.coords + move_to_target(&self.bounds, target, &mut obstacles, self.blueprint.speed)
+		let mut obstacles = w.near(&self.bounds).filter(|o| o.blueprint.genus != Genus::Plant(Size::Small)).map(|o| &o.bounds);
+		self.bounds.coords + move_to_target(&self.bounds, target, &mut obstacles, self.speed(w.time))
 	}
 
-	fn plan(&self, w : &World) -> TaskUpd {
-		if self.tasks.is_empty() {
+	// `taken` holds objects already claimed by someone else this tick
+	fn plan(&self, w : &World, taken : &HashSet<ObjId>) -> TaskUpd {
+		if self.due_birth(w.time) {
+			self.spawn_offspring(w)
+		} else if self.tasks.is_empty() || self.stunned(w.time) {
 			TaskWait
+		} else if self.asleep(w.time) && !self.fleeing() { // alarms wake sleepers up
+			TaskWait
+		} else if let Some(predator) = self.spot_predator(w) {
+			if self.screamed(w.time) { // the alarm is raised already, it runs without another one
+				TaskPush(Task::Flee { from : predator.bounds.coords, until : w.time + FLEE_TIME })
+			} else {
+				TaskAct(Action::Scream { bounds : CircleBounds { r : SCREAM_RANGE, ..self.bounds }, threat : predator.bounds.coords })
+			}
 		} else {
 			match &self.tasks.last().unwrap() {
-				Task::Wander() => gen_circle_bounds(&w.size, None, &w.objects, &self.blueprint).
-					map( |b| TaskPush(Task::GetTo(b))).unwrap_or(TaskWait),
+				Task::Wander => if self.wants_to_mate(w) {
+					TaskPush(Task::Reproduce)
+				} else if let Some(genus) = self.blueprint.forage.as_ref().filter(|_| self.satiety < WELL_FED) {
+					TaskPush(Task::Eat(genus.clone()))
+				} else {
+					gen_circle_bounds(w, None, &self.blueprint).map( |b| TaskPush(Task::GetTo(b))).unwrap_or(TaskWait)
+				},
 				Task::GetTo(target) => if target.collides_with(&self.bounds) { TaskPop } else {
 					TaskAct(Action::MoveTo(self.move_to(w, &target.coords)))
 				},
+				// nobody eats once sated, eating pushed over another task ends there
 				Task::Eat(genus) => {
-					if let Some((i, food)) = w.objects.iter().enumerate().filter( |(_, obj)| obj.blueprint.genus == *genus).
-						min_by_key( |(_, obj)| OrderedFloat(obj.bounds.coords.dist(&self.bounds.coords))) {
+					if self.tasks.len() > 1 && self.satiety >= SATED {
+						TaskPop
+					} else if self.wants_to_mate(w) {
+						TaskPush(Task::Reproduce)
+					} else if self.satiety >= SATED {
+						TaskWait
+					} else if let Some(food) = self.perceive(w, |obj| obj.blueprint.genus == *genus && !taken.contains(&obj.id)) {
 						if food.bounds.collides_with(&self.bounds) {
-							TaskAct(Action::Swallow(i))
+							TaskAct(Action::Swallow(food.id))
 						} else {
 							TaskPush(Task::GetTo(food.bounds.clone()))
 						}
 					} else {
-						TaskPop
+						self.roam(w)
 					}
 				},
 				Task::Hunt(genus) => {
-					if let Some((i, food)) = w.objects.iter().enumerate().filter( |(_, obj)| obj.blueprint.genus == *genus).
-						min_by_key( |(_, obj)| OrderedFloat(obj.bounds.coords.dist(&self.bounds.coords))) {
-						if food.bounds.collides_with(&self.bounds) {
-							TaskAct(Action::Swallow(i))
-						} else {
-							TaskAct(Action::MoveTo(self.move_to(w, &food.bounds.coords)))
+					if self.wants_to_mate(w) {
+						TaskPush(Task::Reproduce)
+					} else if let Some(leader) = self.leader(w) {
+						match (leader.leader(w), leader.tasks.last()) {
+							(Some(top), _) => TaskAct(Action::Follow(top.id)),
+							(_, Some(Task::Chase { prey, until })) if !taken.contains(prey) => TaskPush(Task::Chase { prey : *prey, until : *until }),
+							_ => if leader.bounds.coords.dist(&self.bounds.coords) > FOLLOW_DIST {
+								TaskAct(Action::MoveTo(self.move_to(w, &leader.bounds.coords)))
+							} else { TaskWait }
 						}
+					} else if let Some(leader) = self.find_leader(w) {
+						TaskAct(Action::Follow(leader.id))
+					} else if self.satiety >= SATED { // followers still go along with their leader
+						TaskWait
+					} else if let Some(prey) = self.perceive(w, |obj| obj.blueprint.genus == *genus && !taken.contains(&obj.id) && !self.gave_up_on(obj.id, w.time)).
+						or_else(|| self.perceive(w, |obj| obj.carrion() && !taken.contains(&obj.id))) {
+						TaskPush(Task::Chase { prey : prey.id, until : w.time + CHASE_TIME })
 					} else {
-						TaskPop
+						self.roam(w)
 					}
 				},
+				// sticks to one prey until it's caught, out of sight, taken by someone else or the chase takes too long
+				Task::Chase { prey, until } => match w.objects.get(prey) {
+					Some(prey) if w.time >= *until => TaskAct(Action::GiveUp(prey.id)),
+					Some(prey) if !taken.contains(&prey.id) && self.bounds.coords.dist(&prey.bounds.coords) <= self.perception(w.time) =>
+						if prey.bounds.collides_with(&self.bounds) {
+							TaskAct(Action::Swallow(prey.id))
+						} else {
+							let target = intercept(&self.bounds.coords, self.speed(w.time), &prey.bounds.coords, &prey.velocity);
+							TaskAct(Action::MoveTo(self.move_to(w, &self.flank(w, prey, target))))
+						},
+					_ => TaskPop
+				},
 				Task::Reproduce => {
 					match self.blueprint.genus {
 						Genus::Plant(_) => {
-							if rng_range(&(0.0..1.0)) < 0.005 {
-								let new_b = &CircleBounds { r: self.bounds.r * 4.0, ..self.bounds };
-								gen_circle_bounds(&w.size, Some(&new_b), &w.objects, &self.blueprint).
-									map(|b| TaskAct(Action::Spawn(GameObj::from(&self.blueprint, b, w.time)))).unwrap_or(TaskWait)
-							} else { TaskWait }
+							let chance = PLANT_SPREAD[season(w.time) as usize] * w.soil.fertility(&self.bounds.coords) / FRAME_MOMENT as Amount;
+							if w.rng_range(&(0.0..1.0)) < chance && !self.crowded(w) { self.spawn_offspring(w) } else { TaskWait }
+						},
+						Genus::Animal(_, _) => {
+							match self.find_mate(w) {
+								Some(mate) if self.fertile(w.time) => if mate.bounds.collides_with(&self.bounds) {
+									TaskAct(Action::Mate(mate.id))
+								} else {
+									TaskAct(Action::MoveTo(self.move_to(w, &mate.bounds.coords)))
+								},
+								_ => TaskPop
+							}
 						},
-						Genus::Animal(_, _) => { println!("TODO!"); TaskWait },
+						Genus::Carrion(_) => TaskPop,
 					}
 				}
-				Task::ListenControls() => {
+				Task::Feud => {
+					if let Some(enemy) = self.clan.and_then(|_| self.perceive(w, |obj| self.hostile_to(obj))) {
+						if let Some(technique) = self.choose_technique(enemy, w.time) {
+							TaskAct(Action::Strike { technique, aim : enemy.bounds.coords - self.bounds.coords })
+						} else if enemy.bounds.collides_with(&self.bounds) {
+							self.hit(enemy)
+						} else {
+							TaskAct(Action::MoveTo(self.move_to(w, &enemy.bounds.coords)))
+						}
+					} else if self.wants_to_mate(w) {
+						TaskPush(Task::Reproduce)
+					} else if let Some(slot) = self.remedy().filter(|_| self.wounded(w.time)) {
+						TaskAct(Action::EatItem(slot))
+					} else if let Some(slot) = self.meal().filter(|_| self.satiety < WELL_FED) {
+						TaskAct(Action::EatItem(slot))
+					} else if let Some(genus) = self.blueprint.forage.as_ref().filter(|_| self.satiety < WELL_FED) {
+						TaskPush(Task::Eat(genus.clone()))
+					} else if self.can_meditate() && w.rng_range(&(0.0..1.0)) < 0.5 {
+						TaskPush(Task::Meditate { until : w.time + MEDITATION })
+					} else if self.has_room() && self.perceive(w, |obj| obj.gatherable(w)).is_some() {
+						TaskPush(Task::Gather)
+					} else {
+						gen_circle_bounds(w, None, &self.blueprint).map( |b| TaskPush(Task::GetTo(b))).unwrap_or(TaskWait)
+					}
+				},
+				Task::Gather => match self.perceive(w, |obj| obj.gatherable(w) && !taken.contains(&obj.id)) {
+					Some(item) if self.has_room() => if item.bounds.collides_with(&self.bounds) {
+						TaskAct(Action::Pick(item.id))
+					} else {
+						TaskPush(Task::GetTo(item.bounds.clone()))
+					},
+					_ => TaskPop
+				},
+				Task::Meditate { until } => if w.time >= *until { TaskPop } else { TaskAct(Action::Meditate) },
+				Task::Flee { from, until } => if w.time >= *until { TaskPop } else {
+					let away = self.bounds.coords + (self.bounds.coords - *from).norm().multf(SCREAM_RANGE);
+					TaskAct(Action::MoveTo(self.move_to(w, &w.clamp(&away))))
+				},
+				Task::ListenControls => {
 					TaskWait
 				}
 			}
@@ -236,44 +806,381 @@ impl GameObj {
 	}
 }
 
+impl TaskUpd {
+	// exclusive actions: only one object per tick may succeed on the same target
+	fn claim(&self) -> Option<ObjId> {
+		match self {
+			TaskAct(Action::Swallow(id)) | TaskAct(Action::Pick(id)) => Some(*id),
+			_ => None
+		}
+	}
+}
+
 impl World {
+	// the claimer closest to the target wins, ties go to the lower id
+	fn contest_losers(&self, plans : &BTreeMap<ObjId, TaskUpd>) -> Vec<ObjId> {
+		let mut claims : BTreeMap<ObjId, Vec<ObjId>> = BTreeMap::new();
+		plans.iter().for_each(|(id, upd)| if let Some(target) = upd.claim() { claims.entry(target).or_default().push(*id) });
+		claims.into_iter().filter(|(_, claimers)| claimers.len() > 1).flat_map(|(target, claimers)| {
+			let coords = self.objects[&target].bounds.coords;
+			let winner = *claimers.iter().min_by_key(|id| (OrderedFloat(self.objects[id].bounds.coords.dist(&coords)), **id)).unwrap();
+			claimers.into_iter().filter(move |id| *id != winner)
+		}).collect()
+	}
+
+	// losers of a contest re-plan with the contested targets excluded instead of wasting the tick
+	fn plan_all(&self) -> BTreeMap<ObjId, TaskUpd> {
+		let mut taken : HashSet<ObjId> = HashSet::new();
+		let mut plans : BTreeMap<ObjId, TaskUpd> = self.objects.iter().map( |(id, o)| (*id, o.plan(&self, &taken))).collect();
+		for _ in 0..MAX_REPLANS {
+			let losers = self.contest_losers(&plans);
+			if losers.is_empty() { break }
+			taken.extend(plans.values().filter_map(|upd| upd.claim()));
+			losers.into_iter().for_each(|id| { plans.insert(id, self.objects[&id].plan(&self, &taken)); });
+		}
+		self.contest_losers(&plans).into_iter().for_each(|id| { plans.insert(id, TaskWait); });
+		plans
+	}
+
+	// animals and humanoids caught in the shape except the user and its clan, does nothing if the technique isn't ready
+	pub fn strike(&mut self, id : ObjId, technique : TechniqueId, aim : &Direction) {
+		let (user, data, time) = (&self.objects[&id], &GAME_DATA.techniques[technique], self.time);
+		if !user.ready(technique, time) { return }
+		let (origin, damage) = (user.bounds.coords, data.damage * user.realm_factor());
+		let victims : Vec<ObjId> = self.near(&CircleBounds { coords : origin, r : data.shape.reach() }).
+			filter(|o| o.id != id && o.animal() && (user.clan.is_none() || o.clan != user.clan) && data.shape.covers(&origin, aim, &o.bounds)).
+			map(|o| o.id).collect();
+		victims.into_iter().for_each(|victim| {
+			let victim = self.obj_mut(victim);
+			victim.durability -= damage;
+			victim.tmp_effects.extend(data.on_hit.iter().map(|on_hit| TemporalEffect::new(time, on_hit.duration, on_hit.effect.clone())));
+		});
+		let user = self.obj_mut(id);
+		user.qi -= data.qi_cost;
+		user.tmp_effects.push(TemporalEffect::new(time, data.cooldown, EffectType::Cooldown(technique)));
+	}
+
+	fn can_pick(&self, id : ObjId, item : ObjId) -> bool {
+		match (self.objects.get(&id), self.objects.get(&item)) {
+			(Some(obj), Some(item)) => obj.has_room() && item.blueprint.portable && item.bounds.collides_with(&obj.bounds),
+			_ => false
+		}
+	}
+
+	// the picked object is left in place for the caller to remove
+	fn stow(&mut self, id : ObjId, item : ObjId) {
+		let item = &self.objects[&item];
+		let item = Item { blueprint : item.blueprint, durability : item.durability };
+		self.obj_mut(id).inventory.push(item);
+	}
+
+	// the inventory actions do nothing when they can't be done, so the player may try them at any time
+	pub fn pick_up(&mut self, id : ObjId, item : ObjId) {
+		if self.can_pick(id, item) {
+			self.stow(id, item);
+			self.remove(item);
+		}
+	}
+
+	pub fn drop_item(&mut self, id : ObjId, slot : usize) {
+		let (owner, item) = match self.objects.get(&id).and_then(|obj| obj.inventory.get(slot).map(|item| (obj, item))) {
+			Some(found) => found,
+			None => return
+		};
+		if let Some(bounds) = gen_circle_bounds(self, Some(&CircleBounds { r : owner.bounds.r * 4.0, ..owner.bounds }), item.blueprint) {
+			let mut dropped = GameObj::from(item.blueprint, bounds, self);
+			dropped.durability = item.durability;
+			self.obj_mut(id).inventory.remove(slot);
+			self.insert(dropped);
+		}
+	}
+
+	pub fn eat_item(&mut self, id : ObjId, slot : usize) {
+		let time = self.time;
+		if let Some(obj) = self.objects.get_mut(&id).filter(|obj| slot < obj.inventory.len()) {
+			let item = obj.inventory.remove(slot);
+			obj.feed(item.blueprint.nutrition);
+			obj.heal(item.blueprint.healing, time);
+		}
+	}
+
+	pub fn give_item(&mut self, id : ObjId, slot : usize, to : ObjId) {
+		let can_give = match (self.objects.get(&id), self.objects.get(&to)) {
+			(Some(giver), Some(taker)) => id != to && slot < giver.inventory.len() && taker.has_room() && taker.bounds.collides_with(&giver.bounds),
+			_ => false
+		};
+		if can_give {
+			let item = self.obj_mut(id).inventory.remove(slot);
+			self.obj_mut(to).inventory.push(item);
+		}
+	}
+
+	// carcasses hold as much food as the living did, carried items are scattered around
+	fn leave_remains(&mut self, dead : &GameObj) {
+		let around = CircleBounds { r : dead.bounds.r * 2.0, ..dead.bounds.clone() };
+		let remains = dead.blueprint.remains_ids.iter().map(|id| (&GAME_DATA.blueprints[*id], None)).
+			chain(dead.inventory.iter().map(|item| (item.blueprint, Some(item.durability))));
+		remains.for_each(|(blueprint, durability)| {
+			let bounds = gen_circle_bounds(self, Some(&around), blueprint).unwrap_or_else(|| dead.bounds.clone());
+			let mut obj = GameObj::from(blueprint, bounds, self);
+			if obj.carrion() { obj.durability = dead.blueprint.nutrition }
+			durability.iter().for_each(|durability| obj.durability = *durability);
+			self.insert(obj);
+		});
+	}
+
 	pub fn upd(&mut self) {
-		let mut removed_objects : HashSet<usize> = HashSet::new();
-		let plans : Vec<(usize, TaskUpd)> = self.objects.iter().enumerate().map( |(i, o)| (i, o.plan(&self))).collect();
+		let mut removed_objects : HashSet<ObjId> = HashSet::new();
+		let mut mated : HashSet<ObjId> = HashSet::new(); // so partners who planned to mate with each other conceive once
+		let plans = self.plan_all();
+		self.objects.values_mut().for_each(|obj| obj.velocity = Point::init());
 		plans.into_iter().for_each(|(i, upd)| match upd {
-				TaskUpd::TaskPop => { self.objects[i.clone()].tasks.pop(); },
-				TaskUpd::TaskPush(task) => self.objects[i.clone()].tasks.push(task),
+				TaskUpd::TaskPop => { self.obj_mut(i).tasks.pop(); },
+				TaskUpd::TaskPush(task) => self.obj_mut(i).tasks.push(task),
 				TaskUpd::TaskWait => {},
 				TaskUpd::TaskAct(action) => match action {
-					Action::Scream { .. } => {},
-					Action::Hit { .. } => {},
-					Action::Swallow(i) => if removed_objects.contains(&i) { /*TODO*/ } else { removed_objects.insert(i.clone()); },
-					Action::Pick(i) => if removed_objects.contains(&i) { /*TODO*/ } else { /*TODO add inventory*/ removed_objects.insert(i.clone()); },
-					Action::MoveTo(point) => self.objects[i].bounds.coords = point,
-					Action::Spawn(obj) => self.objects.push(obj)
+					Action::Scream { bounds, threat } => {
+						let (time, listeners) : (FrameCount, Vec<ObjId>) = (self.time, self.near(&bounds).filter(|o| o.bounds.collides_with(&bounds)).map(|o| o.id).collect());
+						listeners.into_iter().for_each(|id| self.obj_mut(id).hear_alarm(threat, time));
+						self.obj_mut(i).tmp_effects.push(TemporalEffect::new(time, SCREAM_COOLDOWN, EffectType::Screamed));
+					},
+					Action::Hit { target, damage } => self.obj_mut(target).durability -= damage,
+					Action::Strike { technique, aim } => self.strike(i, technique, &aim),
+					Action::Swallow(food) => if !removed_objects.contains(&food) {
+						removed_objects.insert(food);
+						if self.objects[&food].plant() { self.soil.change(&self.objects[&food].bounds.coords, -GRAZING) }
+						let (nutrition, eater) = (self.objects[&food].nutrition(), &self.objects[&i]);
+						let eaters : Vec<ObjId> = match eater.pack() {
+							Some(pack) => self.near(&CircleBounds { r : eater.blueprint.perception, ..eater.bounds }).
+								filter(|o| o.pack() == Some(pack) && o.durability > 0.0 && !removed_objects.contains(&o.id)).map(|o| o.id).collect(),
+							None => vec![i]
+						};
+						let share = nutrition / eaters.len() as Amount;
+						eaters.into_iter().for_each(|id| self.obj_mut(id).feed(share));
+					},
+					Action::Pick(item) => if !removed_objects.contains(&item) && self.can_pick(i, item) {
+						removed_objects.insert(item);
+						self.stow(i, item);
+					},
+					Action::EatItem(slot) => self.eat_item(i, slot),
+					Action::Mate(mate) => if !removed_objects.contains(&mate) && !mated.contains(&i) && !mated.contains(&mate) {
+						mated.extend(&[i, mate]);
+						let due = self.time + self.objects[&i].blueprint.gestation;
+						self.obj_mut(i).tmp_effects.push(TemporalEffect::new(due, FRAME_FOREVER, EffectType::Gestation));
+					},
+					Action::Follow(leader) => self.obj_mut(i).leader = Some(leader),
+					Action::GiveUp(prey) => {
+						let (time, obj) = (self.time, self.obj_mut(i));
+						obj.tasks.pop();
+						obj.tmp_effects.push(TemporalEffect::new(time, GIVE_UP_TIME, EffectType::GaveUp(prey)));
+					},
+					Action::Meditate => if self.time % FRAME_MOMENT == 0 { // qi comes in once per moment, as hunger does
+						let (time, roll) = (self.time, self.rng_range(&(0.0..1.0)));
+						self.obj_mut(i).meditate(time, roll);
+					},
+					Action::MoveTo(point) => self.move_obj(i, point),
+					Action::Spawn(obj) => { // for animals that's a birth, which ends the gestation
+						self.obj_mut(i).tmp_effects.retain(|eff| eff.effect != EffectType::Gestation);
+						self.insert(obj);
+					}
 				},
 			});
 
 		let time = self.time;
-		self.objects.iter_mut().enumerate().for_each(|(i, obj)| {
-// TODO: die from hunger and from damage, add hunger
-			obj.tmp_effects.retain(|eff| eff.start + eff.duration >= time);
-			obj.tmp_effects.iter().filter(|eff| eff.start <= time).for_each(|eff| {
-				// TODO: fill missing branches
+		let mut died : BTreeSet<ObjId> = BTreeSet::new();
+		let mut grown : Vec<(ObjId, Dist)> = vec![];
+		let (soil, new_moment) = (&mut self.soil, time % FRAME_MOMENT == 0);
+		if new_moment { soil.regrow(SOIL_REGROWTH) }
+		self.objects.iter_mut().for_each(|(i, obj)| {
+			if new_moment && obj.plant() { soil.change(&obj.bounds.coords, -PLANT_DRAIN * obj.bounds.r.powi(2)) }
+			if new_moment { // per tick the change is small enough to get lost in rounding
+				let vigor_change = obj.vigor(time) - obj.vigor(time.saturating_sub(FRAME_MOMENT));
+				obj.durability += obj.blueprint.durability * obj.realm_factor() * vigor_change;
+			}
+			let r = obj.grown_radius(time);
+			if (r - obj.bounds.r).abs() >= GROWTH_STEP { grown.push((*i, r)) }
+			obj.tmp_effects.retain(|eff| eff.start.saturating_add(eff.duration) >= time);
+			let (mut drain, mut damage) = (0.0, 0.0);
+			for eff in obj.tmp_effects.iter().filter(|eff| eff.start <= time) {
 				match &eff.effect {
 					EffectType::PeriodicEffect { period, effect } => if ((time - eff.start) % period) == 0 {
 						match effect {
-							PeriodicEffectType::Hunger(_) => {},
-							PeriodicEffectType::Damage(_) => {},
+							PeriodicEffectType::Hunger(OrderedFloat(amount)) => drain += amount * COLD_HUNGER[season(time) as usize],
+							PeriodicEffectType::Damage(OrderedFloat(amount)) => damage += amount,
 						}
 					},
-					EffectType::Slow(_) => {},
-					EffectType::Stun => {},
-					EffectType::DelayedDeath => { println!("DEATH!");removed_objects.insert(i); },
+					EffectType::Slow(_) | EffectType::Stun => {}, // see GameObj::speed and GameObj::plan
+					EffectType::DelayedDeath => { died.insert(*i); },
+					EffectType::Gestation => {}, // see GameObj::plan
+					EffectType::Cooldown(_) => {}, // see GameObj::ready
+					EffectType::GaveUp(_) | EffectType::Screamed => {}, // see GameObj::plan
 				}
-			});
+			}
+			if drain > 0.0 { obj.starve(drain) }
+			if obj.carrion() { soil.change(&obj.bounds.coords, damage * ROT_FERTILITY) }
+			obj.durability -= damage;
+			if obj.durability <= 0.0 { died.insert(*i); }
 		});
+		grown.into_iter().for_each(|(i, r)| self.resize(i, r));
 		self.time += 1;
-		removed_objects.iter().sorted_by_key(|i| -(**i as i32)).for_each( |i| { self.objects.remove(*i); });
+		removed_objects.iter().for_each( |i| { self.remove(*i); });
+		died.into_iter().for_each(|i| if let Some(dead) = self.remove(i) { self.leave_remains(&dead) });
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::collision::GRID_CELL_SIZE;
+	use crate::game_data::load_for_tests;
+
+	const NOON : FrameCount = FRAME_DAY / 2; // everyone is awake
+
+	fn world() -> World {
+		load_for_tests();
+		let mut w = World::new(Point::new(200.0, 200.0), GRID_CELL_SIZE, 1);
+		w.time = NOON;
+		w
+	}
+
+	// a grown up that only does what it's told
+	fn add(w : &mut World, name : &str, x : f32, y : f32, tasks : Vec<Task>) -> ObjId {
+		let blueprint = GameObjBlueprint::by_name(name).unwrap();
+		let mut obj = GameObj::from(blueprint, CircleBounds { coords : Point::new(x, y), r : blueprint.radius.end }, w);
+		obj.starting_age = blueprint.maturity;
+		obj.durability = blueprint.durability;
+		obj.tasks = tasks;
+		w.insert(obj)
+	}
+
+	fn count(w : &World, name : &str) -> usize { w.objects.values().filter(|o| o.blueprint.name == name).count() }
+
+	#[test]
+	fn objects_at_zero_durability_are_removed() {
+		let mut w = world();
+		let tree = add(&mut w, "Tree", 50.0, 50.0, vec![]);
+		let grass = add(&mut w, "Grass", 150.0, 150.0, vec![]);
+		w.obj_mut(tree).durability = 0.0;
+		w.upd();
+		assert!(!w.objects.contains_key(&tree));
+		assert!(w.objects.contains_key(&grass));
+	}
+
+	#[test]
+	fn hunger_eats_into_durability_once_satiety_runs_out() {
+		let mut w = world();
+		let hare = add(&mut w, "Hare", 50.0, 50.0, vec![]);
+		w.obj_mut(hare).satiety = 1.0;
+		for _ in 0..FRAME_MOMENT * 2 { w.upd(); }
+		let hare = &w.objects[&hare];
+		assert_eq!(hare.satiety, 0.0);
+		assert!(hare.durability < hare.blueprint.durability);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn starving_to_death_leaves_a_carcass() {
+		let mut w = world();
+		let hare = add(&mut w, "Hare", 50.0, 50.0, vec![]);
+		w.obj_mut(hare).satiety = 0.0;
+		w.obj_mut(hare).durability = 1.0;
+		for _ in 0..FRAME_MOMENT { w.upd(); }
+		assert!(!w.objects.contains_key(&hare));
+		assert_eq!(count(&w, "Carcass"), 1);
+	}
+
+	#[test]
+	fn swallowing_feeds_the_eater() {
+		let mut w = world();
+		let hare = add(&mut w, "Hare", 50.0, 50.0, vec![Task::Eat(Genus::Plant(Size::Small))]);
+		let grass = add(&mut w, "Grass", 51.0, 50.0, vec![]);
+		w.obj_mut(hare).satiety = 50.0;
+		w.upd();
+		assert!(!w.objects.contains_key(&grass));
+		assert!(w.objects[&hare].satiety > 50.0);
+	}
+
+	#[test]
+	fn the_closest_eater_wins_a_contested_meal() {
+		let mut w = world();
+		let food = Task::Eat(Genus::Plant(Size::Small));
+		let far = add(&mut w, "Hare", 47.0, 50.0, vec![food.clone()]);
+		let near = add(&mut w, "Hare", 51.0, 50.0, vec![food]);
+		add(&mut w, "Grass", 50.0, 50.0, vec![]);
+		w.objects.values_mut().for_each(|hare| hare.satiety = 50.0);
+		w.upd();
+		assert_eq!(count(&w, "Grass"), 0);
+		assert!(w.objects[&near].satiety > w.objects[&far].satiety);
+		// the loser planned something else instead of wasting the tick
+		assert!(w.objects[&far].tasks.len() > 1);
+	}
+
+	#[test]
+	fn partners_conceive_once_and_give_birth_when_due() {
+		let mut w = world();
+		let a = add(&mut w, "Hare", 50.0, 50.0, vec![Task::Reproduce]);
+		let b = add(&mut w, "Hare", 52.0, 50.0, vec![Task::Reproduce]);
+		w.upd();
+		assert_eq!(w.objects.values().filter(|hare| hare.pregnant()).count(), 1);
+		let mother = if w.objects[&a].pregnant() { a } else { b };
+		w.objects.values_mut().for_each(|hare| hare.tasks.clear());
+		w.time += GameObjBlueprint::by_name("Hare").unwrap().gestation;
+		w.upd();
+		assert_eq!(count(&w, "Hare"), 3);
+		assert!(!w.objects[&mother].pregnant());
+	}
+
+	#[test]
+	fn a_birth_without_room_waits_instead_of_getting_lost() {
+		let mut w = world();
+		let mother = add(&mut w, "Hare", 50.0, 50.0, vec![]);
+		let time = w.time;
+		w.obj_mut(mother).tmp_effects.push(TemporalEffect::new(time, FRAME_FOREVER, EffectType::Gestation));
+		let walls : Vec<ObjId> = (0..8).map(|i| {
+			let angle = i as f32 * std::f32::consts::PI / 4.0;
+			add(&mut w, "Tree", 50.0 + angle.cos() * 20.0, 50.0 + angle.sin() * 20.0, vec![])
+		}).collect();
+		w.upd();
+		assert_eq!(count(&w, "Hare"), 1);
+		walls.into_iter().for_each(|id| { w.remove(id); });
+		w.upd();
+		assert_eq!(count(&w, "Hare"), 2);
+	}
+
+	#[test]
+	fn feuding_wanderers_hit_their_enemies_only() {
+		let mut w = world();
+		let clan = |name| GAME_DATA.clan_by_name(name);
+		let (wudang, emei, ally) = (
+			add(&mut w, "Wanderer", 50.0, 50.0, vec![Task::Feud]),
+			add(&mut w, "Wanderer", 56.0, 50.0, vec![]),
+			add(&mut w, "Wanderer", 50.0, 56.0, vec![])
+		);
+		let grass = add(&mut w, "Grass", 56.0, 50.0, vec![]);
+		w.obj_mut(wudang).clan = clan("Wudang");
+		w.obj_mut(emei).clan = clan("Emei");
+		w.obj_mut(ally).clan = clan("Wudang");
+		w.upd();
+		let damage = w.objects[&wudang].blueprint.damage;
+		assert_eq!(w.objects[&emei].durability, w.objects[&emei].blueprint.durability - damage);
+		assert_eq!(w.objects[&ally].durability, w.objects[&ally].blueprint.durability);
+		assert_eq!(w.objects[&grass].durability, w.objects[&grass].blueprint.durability);
+	}
+
+	#[test]
+	fn strikes_spare_the_user_its_clan_plants_and_carcasses() {
+		let mut w = world();
+		let clan = GAME_DATA.clan_by_name("Wudang");
+		let user = add(&mut w, "Wanderer", 50.0, 50.0, vec![]);
+		let (foe, ally) = (add(&mut w, "Wanderer", 60.0, 50.0, vec![]), add(&mut w, "Wanderer", 50.0, 60.0, vec![]));
+		let others = vec![add(&mut w, "Grass", 55.0, 55.0, vec![]), add(&mut w, "Carcass", 45.0, 45.0, vec![])];
+		w.obj_mut(user).clan = clan;
+		w.obj_mut(ally).clan = clan;
+		w.obj_mut(user).qi = 100.0;
+		let sweep = Technique::by_name("Sword Sweep").unwrap();
+		w.strike(user, sweep, &Point::new(1.0, 0.0));
+		let hurt = |id : ObjId| w.objects[&id].durability < w.objects[&id].blueprint.durability;
+		assert!(hurt(foe));
+		assert!(!hurt(user) && !hurt(ally) && !others.into_iter().any(hurt));
+		assert!(!w.objects[&user].ready(sweep, w.time));
+	}
+}